@@ -0,0 +1,228 @@
+use ark_ff::PrimeField;
+use std::collections::HashMap;
+
+use super::r1cs::SparseMatrix;
+use super::{Error, CCS};
+
+/// A trace column in the current or the next row
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TraceCell {
+    Current(usize),
+    Next(usize),
+}
+
+/// sum_i c_i * prod_{cell in S_i} cell, an empty S_i is the constant c_i
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transition<F: PrimeField> {
+    pub terms: Vec<(F, Vec<TraceCell>)>,
+}
+
+/// Every transition must vanish on each pair of consecutive rows, and the boundary
+/// constraints (row, column, value) pin cells to public values.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Air<F: PrimeField> {
+    pub width: usize,
+    pub transitions: Vec<Transition<F>>,
+    pub boundary: Vec<(usize, usize, F)>,
+}
+
+impl<F: PrimeField> Air<F> {
+    pub fn validate(&self, num_rows: usize) -> Result<(), Error<F>> {
+        for t in &self.transitions {
+            for cell in t.terms.iter().flat_map(|(_, s)| s) {
+                let (TraceCell::Current(j) | TraceCell::Next(j)) = cell;
+                if *j >= self.width {
+                    return Err(Error::InvalidTraceColumn(*j, self.width));
+                }
+            }
+        }
+        for (i, j, _) in &self.boundary {
+            if *i >= num_rows || *j >= self.width {
+                return Err(Error::InvalidCell(*j, *i));
+            }
+        }
+        Ok(())
+    }
+
+    /// z = (1, x, w) where x are the boundary values and w the trace row by row,
+    /// trace[i][j] being column j at row i
+    pub fn trace_to_z(&self, trace: &[Vec<F>]) -> Result<Vec<F>, Error<F>> {
+        self.validate(trace.len())?;
+        let mut z = vec![F::one()];
+        z.extend(self.boundary.iter().map(|(_, _, v)| *v));
+        for row in trace {
+            if row.len() != self.width {
+                return Err(Error::InvalidVectorLength(self.width, row.len()));
+            }
+            z.extend(row);
+        }
+        Ok(z)
+    }
+}
+
+impl<F: PrimeField> CCS<F> {
+    /// AIR to CCS as in the CCS paper, for a trace of num_rows rows. Transition k is
+    /// checked in the k-th block of num_rows - 1 rows, with one matrix per trace cell it
+    /// uses, selecting that cell of row i or i + 1 at row i of the block. A last block
+    /// checks cell - x_b = 0 for every boundary constraint b.
+    pub fn from_air(air: &Air<F>, num_rows: usize) -> Result<Self, Error<F>> {
+        air.validate(num_rows)?;
+        let l = air.boundary.len();
+        let n = 1 + l + air.width * num_rows;
+        let steps = num_rows.saturating_sub(1);
+        let m = air.transitions.len() * steps + l;
+        let column = |i: usize, j: usize| 1 + l + i * air.width + j;
+
+        let mut m_vec = Vec::new();
+        let mut s_vec = Vec::new();
+        let mut v = Vec::new();
+        for (k, transition) in air.transitions.iter().enumerate() {
+            // None is the constant one
+            let mut matrices: HashMap<Option<TraceCell>, usize> = HashMap::new();
+            for (c, cells) in &transition.terms {
+                let cells: Vec<Option<TraceCell>> = if cells.is_empty() {
+                    vec![None]
+                } else {
+                    cells.iter().copied().map(Some).collect()
+                };
+                let s = cells
+                    .into_iter()
+                    .map(|cell| {
+                        *matrices.entry(cell).or_insert_with(|| {
+                            let vals = (0..steps)
+                                .map(|i| {
+                                    let col = match cell {
+                                        Some(TraceCell::Current(j)) => column(i, j),
+                                        Some(TraceCell::Next(j)) => column(i + 1, j),
+                                        None => 0,
+                                    };
+                                    (k * steps + i, col, F::one())
+                                })
+                                .collect();
+                            m_vec.push(SparseMatrix {
+                                n_rows: m,
+                                n_cols: n,
+                                vals,
+                            });
+                            m_vec.len() - 1
+                        })
+                    })
+                    .collect();
+                s_vec.push(s);
+                v.push(*c);
+            }
+        }
+
+        if l > 0 || m_vec.is_empty() {
+            let base = air.transitions.len() * steps;
+            let vals = air
+                .boundary
+                .iter()
+                .enumerate()
+                .flat_map(|(b, (i, j, _))| {
+                    [
+                        (base + b, column(*i, *j), F::one()),
+                        (base + b, 1 + b, -F::one()),
+                    ]
+                })
+                .collect();
+            m_vec.push(SparseMatrix {
+                n_rows: m,
+                n_cols: n,
+                vals,
+            });
+            s_vec.push(vec![m_vec.len() - 1]);
+            v.push(F::one());
+        }
+        CCS::new(l, m_vec, s_vec, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_pallas::Fr;
+    use ark_std::One;
+
+    use super::*;
+    use crate::ccs::r1cs::to_f_vec;
+
+    // (a, b) -> (b, a + b), starting at (1, 1) and ending with b = 34 after 8 rows
+    fn get_fibonacci_air() -> Air<Fr> {
+        use TraceCell::{Current, Next};
+        Air {
+            width: 2,
+            transitions: vec![
+                Transition {
+                    terms: vec![(Fr::one(), vec![Next(0)]), (-Fr::one(), vec![Current(1)])],
+                },
+                Transition {
+                    terms: vec![
+                        (Fr::one(), vec![Next(1)]),
+                        (-Fr::one(), vec![Current(0)]),
+                        (-Fr::one(), vec![Current(1)]),
+                    ],
+                },
+            ],
+            boundary: vec![
+                (0, 0, Fr::one()),
+                (0, 1, Fr::one()),
+                (7, 1, Fr::from(34u64)),
+            ],
+        }
+    }
+
+    fn get_fibonacci_trace(num_rows: usize) -> Vec<Vec<Fr>> {
+        let mut trace = vec![to_f_vec(vec![1, 1])];
+        for i in 1..num_rows {
+            let (a, b) = (trace[i - 1][0], trace[i - 1][1]);
+            trace.push(vec![b, a + b]);
+        }
+        trace
+    }
+
+    #[test]
+    fn test_from_air_fibonacci() {
+        let air = get_fibonacci_air();
+        let ccs = CCS::<Fr>::from_air(&air, 8).unwrap();
+        assert_eq!((ccs.m, ccs.n, ccs.l), (2 * 7 + 3, 1 + 3 + 16, 3));
+
+        let trace = get_fibonacci_trace(8);
+        let z = air.trace_to_z(&trace).unwrap();
+        ccs.is_satisfied(&z).unwrap();
+
+        // a broken step
+        let mut wrong = trace.clone();
+        wrong[4][1] += Fr::one();
+        assert!(ccs.is_satisfied(&air.trace_to_z(&wrong).unwrap()).is_err());
+
+        // the trace does not reach the claimed output
+        let mut air_wrong = air.clone();
+        air_wrong.boundary[2].2 = Fr::from(35u64);
+        assert!(ccs
+            .is_satisfied(&air_wrong.trace_to_z(&trace).unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn test_from_air_cubic() {
+        // x' = x^3 + 1, a degree 3 transition with a constant term
+        use TraceCell::{Current, Next};
+        let air = Air {
+            width: 1,
+            transitions: vec![Transition {
+                terms: vec![
+                    (Fr::one(), vec![Next(0)]),
+                    (-Fr::one(), vec![Current(0); 3]),
+                    (-Fr::one(), vec![]),
+                ],
+            }],
+            boundary: vec![(0, 0, Fr::from(2u64))],
+        };
+        let ccs = CCS::<Fr>::from_air(&air, 3).unwrap();
+        assert_eq!(ccs.d, 3);
+        let z = air
+            .trace_to_z(&[to_f_vec(vec![2]), to_f_vec(vec![9]), to_f_vec(vec![730])])
+            .unwrap();
+        ccs.is_satisfied(&z).unwrap();
+    }
+}
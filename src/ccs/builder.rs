@@ -0,0 +1,96 @@
+use ark_ff::PrimeField;
+
+use super::r1cs::SparseMatrix;
+use super::{Error, CCS};
+
+/// Incremental CCS construction: add the matrices, then the terms
+/// c_i * hadamard_{j in S_i} M_j * z, and build computes t, q, d, s and s_prime.
+/// All the matrices must have the same dimensions, z = (1, x, w) with |x| = l.
+pub struct CCSBuilder<F: PrimeField> {
+    l: usize,
+    m_vec: Vec<SparseMatrix<F>>,
+    s_vec: Vec<Vec<usize>>,
+    v: Vec<F>,
+}
+
+impl<F: PrimeField> CCSBuilder<F> {
+    pub fn new(l: usize) -> Self {
+        Self {
+            l,
+            m_vec: Vec::new(),
+            s_vec: Vec::new(),
+            v: Vec::new(),
+        }
+    }
+
+    /// returns the index of the matrix to use in multisets
+    pub fn add_matrix(&mut self, m: SparseMatrix<F>) -> usize {
+        self.m_vec.push(m);
+        self.m_vec.len() - 1
+    }
+
+    /// adds c * hadamard_{j in s} M_j * z, a matrix may appear several times in s
+    pub fn add_multiset(&mut self, c: F, s: Vec<usize>) {
+        self.v.push(c);
+        self.s_vec.push(s);
+    }
+
+    pub fn build(self) -> Result<CCS<F>, Error<F>> {
+        CCS::new(self.l, self.m_vec, self.s_vec, self.v)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use ark_pallas::Fr;
+    use ark_std::One;
+
+    use super::*;
+    use crate::ccs::r1cs::{dense_matrix_to_sparse, to_f_matrix, to_f_vec};
+
+    // y = x^5 + 3 and v = u^5 + 3, one degree 5 gate per row, z = (1, y, x, u, v)
+    pub fn get_test_degree_5_ccs<F: PrimeField>() -> CCS<F> {
+        let mut builder = CCSBuilder::<F>::new(1);
+        let input = builder.add_matrix(dense_matrix_to_sparse(to_f_matrix(vec![
+            vec![0, 0, 1, 0, 0],
+            vec![0, 0, 0, 1, 0],
+        ])));
+        let output = builder.add_matrix(dense_matrix_to_sparse(to_f_matrix(vec![
+            vec![0, 1, 0, 0, 0],
+            vec![0, 0, 0, 0, 1],
+        ])));
+        let one = builder.add_matrix(dense_matrix_to_sparse(to_f_matrix(vec![
+            vec![1, 0, 0, 0, 0],
+            vec![1, 0, 0, 0, 0],
+        ])));
+        builder.add_multiset(F::one(), vec![input; 5]);
+        builder.add_multiset(-F::one(), vec![output]);
+        builder.add_multiset(F::from(3u64), vec![one]);
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn test_builder_degree_5_gate() {
+        let ccs = get_test_degree_5_ccs::<Fr>();
+        assert_eq!((ccs.m, ccs.n, ccs.l), (2, 5, 1));
+        assert_eq!((ccs.t, ccs.q, ccs.d), (3, 3, 5));
+        assert_eq!((ccs.s, ccs.s_prime), (1, 3));
+
+        // 2^5 + 3 = 35, 3^5 + 3 = 246
+        let z: Vec<Fr> = to_f_vec(vec![1, 35, 2, 3, 246]);
+        ccs.is_satisfied(&z).unwrap();
+        let wrong: Vec<Fr> = to_f_vec(vec![1, 35, 2, 3, 245]);
+        assert!(ccs.is_satisfied(&wrong).is_err());
+    }
+
+    #[test]
+    fn test_builder_invalid_multiset() {
+        let mut builder = CCSBuilder::<Fr>::new(1);
+        let a = builder.add_matrix(dense_matrix_to_sparse(to_f_matrix(vec![vec![0, 1]])));
+        builder.add_multiset(Fr::one(), vec![a, a + 1]);
+        assert!(matches!(
+            builder.build(),
+            Err(Error::InvalidMultisetIndex(0, 1, 1))
+        ));
+    }
+}
@@ -0,0 +1,304 @@
+use ark_ff::PrimeField;
+
+use super::labels::Labels;
+use super::r1cs::{SparseMatrix, R1CS};
+use super::{Error, CCS};
+
+/// Where the columns of the two composed z vectors go in the combined
+/// z = (1, x_1, x_2, w_1, w_2), both sharing the constant column 0.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Composition {
+    pub first: Vec<usize>,
+    pub second: Vec<usize>,
+    // number of columns of the combined z
+    pub n: usize,
+}
+
+impl Composition {
+    fn new((n_1, l_1): (usize, usize), (n_2, l_2): (usize, usize)) -> Self {
+        let (w_1, w_2) = (n_1 - l_1 - 1, n_2 - l_2 - 1);
+        let first = (0..n_1)
+            .map(|c| if c <= l_1 { c } else { c + l_2 })
+            .collect();
+        let second = (0..n_2)
+            .map(|c| match c {
+                0 => 0,
+                c if c <= l_2 => c + l_1,
+                c => c + l_1 + w_1,
+            })
+            .collect();
+        Composition {
+            first,
+            second,
+            n: 1 + l_1 + l_2 + w_1 + w_2,
+        }
+    }
+
+    /// combined z from z_1 and z_2, the wired values must already agree
+    pub fn z<F: PrimeField>(&self, z_1: &[F], z_2: &[F]) -> Result<Vec<F>, Error<F>> {
+        for (map, z) in [(&self.first, z_1), (&self.second, z_2)] {
+            if z.len() != map.len() {
+                return Err(Error::InvalidVectorLength(map.len(), z.len()));
+            }
+        }
+        let mut z = vec![F::zero(); self.n];
+        for (map, z_i) in [(&self.first, z_1), (&self.second, z_2)] {
+            for (c, v) in map.iter().zip(z_i) {
+                z[*c] = *v;
+            }
+        }
+        Ok(z)
+    }
+
+    fn validate_wires<F: PrimeField>(&self, wires: &[(usize, usize)]) -> Result<(), Error<F>> {
+        match wires
+            .iter()
+            .find(|(a, b)| *a == 0 || *b == 0 || *a >= self.first.len() || *b >= self.second.len())
+        {
+            Some((a, b)) => Err(Error::InvalidWire(*a, *b)),
+            None => Ok(()),
+        }
+    }
+
+    // labels of both parts at their rows and columns, the rows of the second
+    // starting at m_1
+    fn labels(&self, first: &Labels, second: &Labels, m_1: usize) -> Labels {
+        let mut labels = second.remap(|r| Some(m_1 + r), |c| Some(self.second[c]));
+        let first = first.remap(Some, |c| Some(self.first[c]));
+        labels.rows.extend(first.rows);
+        labels.columns.extend(first.columns);
+        labels
+    }
+
+    // M placed at rows offset.. of an n_rows x n columns matrix, with columns remapped
+    fn embed<F: PrimeField>(
+        m: &SparseMatrix<F>,
+        map: &[usize],
+        offset: usize,
+        n_rows: usize,
+        n_cols: usize,
+    ) -> SparseMatrix<F> {
+        SparseMatrix {
+            n_rows,
+            n_cols,
+            vals: m
+                .vals
+                .iter()
+                .map(|(r, c, v)| (offset + r, map[*c], *v))
+                .collect(),
+        }
+    }
+
+    // z_1[a] - z_2[b] at the rows after offset, one per wire
+    fn wire_rows<F: PrimeField>(
+        &self,
+        wires: &[(usize, usize)],
+        offset: usize,
+        n_rows: usize,
+    ) -> SparseMatrix<F> {
+        SparseMatrix {
+            n_rows,
+            n_cols: self.n,
+            vals: wires
+                .iter()
+                .enumerate()
+                .flat_map(|(i, (a, b))| {
+                    [
+                        (offset + i, self.first[*a], F::one()),
+                        (offset + i, self.second[*b], -F::one()),
+                    ]
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<F: PrimeField> R1CS<F> {
+    /// Block diagonal composition of self and other, the IO of both becomes the IO
+    /// of the result. Every wire (a, b) adds the row (z_1[a] - z_2[b]) * 1 = 0, a and b
+    /// being columns of the z of self and other, usually an output and an input.
+    pub fn compose(
+        &self,
+        other: &Self,
+        wires: &[(usize, usize)],
+    ) -> Result<(Self, Composition), Error<F>> {
+        self.validate()?;
+        other.validate()?;
+        let composition = Composition::new((self.a.n_cols, self.l), (other.a.n_cols, other.l));
+        composition.validate_wires(wires)?;
+        let (m_1, m_2) = (self.a.n_rows, other.a.n_rows);
+        let n_rows = m_1 + m_2 + wires.len();
+        let n_cols = composition.n;
+        let combine = |m_1: &SparseMatrix<F>, m_2: &SparseMatrix<F>| {
+            let mut m = Composition::embed(m_1, &composition.first, 0, n_rows, n_cols);
+            m.vals.extend(
+                Composition::embed(m_2, &composition.second, self.a.n_rows, n_rows, n_cols).vals,
+            );
+            m
+        };
+        let mut a = combine(&self.a, &other.a);
+        let mut b = combine(&self.b, &other.b);
+        let c = combine(&self.c, &other.c);
+        a.vals
+            .extend(composition.wire_rows(wires, m_1 + m_2, n_rows).vals);
+        b.vals.extend((m_1 + m_2..n_rows).map(|r| (r, 0, F::one())));
+        let mut r1cs = R1CS::new(self.l + other.l, a, b, c)?;
+        r1cs.labels = composition.labels(&self.labels, &other.labels, m_1);
+        Ok((r1cs, composition))
+    }
+}
+
+impl<F: PrimeField> CCS<F> {
+    /// Block diagonal composition of self and other, like R1CS::compose. The matrices
+    /// of other come after the ones of self in m_vec, followed by one matrix with a
+    /// z_1[a] - z_2[b] row per wire, added as a degree 1 term. A constant term, with an
+    /// empty multiset, would hold at every row, so it gets a matrix with a one in column
+    /// 0 at the rows of its side only.
+    pub fn compose(
+        &self,
+        other: &Self,
+        wires: &[(usize, usize)],
+    ) -> Result<(Self, Composition), Error<F>> {
+        self.validate()?;
+        other.validate()?;
+        let composition = Composition::new((self.n, self.l), (other.n, other.l));
+        composition.validate_wires(wires)?;
+        let n_rows = self.m + other.m + wires.len();
+        let n_cols = composition.n;
+
+        let mut m_vec: Vec<_> = self
+            .m_vec
+            .iter()
+            .map(|m_j| Composition::embed(m_j, &composition.first, 0, n_rows, n_cols))
+            .collect();
+        m_vec.extend(
+            other
+                .m_vec
+                .iter()
+                .map(|m_j| Composition::embed(m_j, &composition.second, self.m, n_rows, n_cols)),
+        );
+        let mut side_multisets = |ccs: &Self, shift: usize, offset: usize| {
+            let mut constant = None;
+            ccs.s_vec
+                .iter()
+                .map(|s| {
+                    if !s.is_empty() {
+                        return s.iter().map(|j| j + shift).collect();
+                    }
+                    vec![*constant.get_or_insert_with(|| {
+                        m_vec.push(SparseMatrix {
+                            n_rows,
+                            n_cols,
+                            vals: (offset..offset + ccs.m).map(|r| (r, 0, F::one())).collect(),
+                        });
+                        m_vec.len() - 1
+                    })]
+                })
+                .collect::<Vec<Vec<usize>>>()
+        };
+        let mut s_vec = side_multisets(self, 0, 0);
+        s_vec.extend(side_multisets(other, self.t, self.m));
+        let mut v = [self.v.clone(), other.v.clone()].concat();
+        if !wires.is_empty() {
+            m_vec.push(composition.wire_rows(wires, self.m + other.m, n_rows));
+            s_vec.push(vec![m_vec.len() - 1]);
+            v.push(F::one());
+        }
+        let mut ccs = CCS::new(self.l + other.l, m_vec, s_vec, v)?;
+        ccs.labels = composition.labels(&self.labels, &other.labels, self.m);
+        Ok((ccs, composition))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_pallas::Fr;
+
+    use super::*;
+    use crate::ccs::builder::tests::get_test_degree_5_ccs;
+    use crate::ccs::r1cs::tests::{get_test_r1cs, get_test_z};
+    use crate::ccs::r1cs::to_f_vec;
+
+    #[test]
+    fn test_compose_r1cs() {
+        // y = x^3 + x + 5 twice, the output y of the first is the input x of the second
+        let r1cs = get_test_r1cs::<Fr>();
+        let (composed, composition) = r1cs.compose(&r1cs, &[(2, 1)]).unwrap();
+        assert_eq!(
+            (composed.l, composed.a.n_rows, composed.a.n_cols),
+            (2, 9, 11)
+        );
+
+        let z = composition
+            .z(&get_test_z(3), &get_test_z::<Fr>(35))
+            .unwrap();
+        assert_eq!(z[1..3].to_vec(), to_f_vec::<Fr>(vec![3, 35]));
+        composed.is_satisfied(&z).unwrap();
+
+        // both halves are satisfied but the wire is not
+        let z = composition.z(&get_test_z(3), &get_test_z::<Fr>(4)).unwrap();
+        match composed.is_satisfied(&z) {
+            Err(Error::R1CSNotSatisfied(rows)) => assert_eq!(rows[0].row, 8),
+            _ => panic!("expected the wire row to fail"),
+        }
+
+        assert!(matches!(
+            r1cs.compose(&r1cs, &[(6, 1)]),
+            Err(Error::InvalidWire(6, 1))
+        ));
+    }
+
+    #[test]
+    fn test_compose_ccs() {
+        // y = x^3 + x + 5 then v = y^5 + 3, with y wired to the x of the degree 5 gate
+        let first = CCS::<Fr>::from_r1cs(get_test_r1cs()).unwrap();
+        let second = get_test_degree_5_ccs::<Fr>();
+        let (composed, composition) = first.compose(&second, &[(2, 2)]).unwrap();
+        assert_eq!((composed.m, composed.n, composed.l), (7, 10, 2));
+        assert_eq!((composed.t, composed.q, composed.d), (7, 6, 5));
+
+        let z_2: Vec<Fr> = to_f_vec(vec![1, 52521878, 35, 2, 35]);
+        let z = composition.z(&get_test_z(3), &z_2).unwrap();
+        composed.is_satisfied(&z).unwrap();
+
+        let z_2: Vec<Fr> = to_f_vec(vec![1, 35, 2, 3, 246]);
+        let z = composition.z(&get_test_z(3), &z_2).unwrap();
+        assert!(composed.is_satisfied(&z).is_err());
+    }
+
+    #[test]
+    fn test_compose_ccs_constant_term() {
+        // x - 5 = 0 with the constant as an empty multiset, wired to the x of x^3 + x + 5
+        let five = CCS::<Fr>::new(
+            1,
+            vec![SparseMatrix {
+                n_rows: 1,
+                n_cols: 2,
+                vals: vec![(0, 1, Fr::from(1u64))],
+            }],
+            vec![vec![0], vec![]],
+            vec![Fr::from(1u64), -Fr::from(5u64)],
+        )
+        .unwrap();
+        let cubic = CCS::<Fr>::from_r1cs(get_test_r1cs()).unwrap();
+        let z_5: Vec<Fr> = to_f_vec(vec![1, 5]);
+
+        // the constant only holds at the rows of its side, first or second
+        let (composed, composition) = five.compose(&cubic, &[(1, 1)]).unwrap();
+        assert_eq!((composed.m, composed.t), (6, 6));
+        composed
+            .is_satisfied(&composition.z(&z_5, &get_test_z(5)).unwrap())
+            .unwrap();
+        let (composed, composition) = cubic.compose(&five, &[(1, 1)]).unwrap();
+        composed
+            .is_satisfied(&composition.z(&get_test_z(5), &z_5).unwrap())
+            .unwrap();
+
+        let z_4: Vec<Fr> = to_f_vec(vec![1, 4]);
+        let z = composition.z(&get_test_z(4), &z_4).unwrap();
+        assert!(matches!(
+            composed.is_satisfied(&z),
+            Err(Error::NotSatisfied(rows)) if rows.len() == 1 && rows[0].row == 4
+        ));
+    }
+}
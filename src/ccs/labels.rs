@@ -0,0 +1,275 @@
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use super::r1cs::{SparseMatrix, R1CS};
+use super::{Error, CCS};
+
+/// Optional names of the rows and columns of a constraint system, only used to
+/// print it. Unnamed columns print as 1, x_i or w_i and unnamed rows as row r.
+#[derive(Clone, Debug, Default, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Labels {
+    pub rows: BTreeMap<usize, String>,
+    pub columns: BTreeMap<usize, String>,
+}
+
+impl Labels {
+    pub fn row(&self, r: usize) -> String {
+        self.rows
+            .get(&r)
+            .cloned()
+            .unwrap_or_else(|| format!("row {}", r))
+    }
+
+    /// l is the number of public inputs, to tell x from w
+    pub fn column(&self, c: usize, l: usize) -> String {
+        match self.columns.get(&c) {
+            Some(name) => name.clone(),
+            None if c == 0 => "1".to_string(),
+            None if c <= l => format!("x_{}", c - 1),
+            None => format!("w_{}", c - l - 1),
+        }
+    }
+
+    /// every label must name one of the n_rows rows or n_cols columns
+    pub fn validate<F: PrimeField>(&self, n_rows: usize, n_cols: usize) -> Result<(), Error<F>> {
+        if let Some((r, _)) = self.rows.range(n_rows..).next() {
+            return Err(Error::InvalidRowLabel(*r, n_rows));
+        }
+        if let Some((c, _)) = self.columns.range(n_cols..).next() {
+            return Err(Error::InvalidColumnLabel(*c, n_cols));
+        }
+        Ok(())
+    }
+
+    /// keeps the labels of the rows and columns that are mapped to Some index
+    pub fn remap(
+        &self,
+        row: impl Fn(usize) -> Option<usize>,
+        column: impl Fn(usize) -> Option<usize>,
+    ) -> Self {
+        let remap = |labels: &BTreeMap<usize, String>, f: &dyn Fn(usize) -> Option<usize>| {
+            labels
+                .iter()
+                .filter_map(|(i, name)| f(*i).map(|i| (i, name.clone())))
+                .collect()
+        };
+        Labels {
+            rows: remap(&self.rows, &row),
+            columns: remap(&self.columns, &column),
+        }
+    }
+}
+
+// values above (p - 1) / 2 are printed as negative
+fn format_field<F: PrimeField>(v: F) -> String {
+    if v.into_bigint() > F::MODULUS_MINUS_ONE_DIV_TWO {
+        format!("-{}", -v)
+    } else {
+        v.to_string()
+    }
+}
+
+fn row_entries<F: PrimeField>(m: &SparseMatrix<F>, r: usize) -> Vec<(usize, F)> {
+    m.vals
+        .iter()
+        .filter(|(i, _, _)| *i == r)
+        .map(|(_, c, v)| (*c, *v))
+        .collect()
+}
+
+fn format_lc<F: PrimeField>(entries: &[(usize, F)], labels: &Labels, l: usize) -> String {
+    if entries.is_empty() {
+        return "0".to_string();
+    }
+    let terms: Vec<String> = entries
+        .iter()
+        .map(|(c, v)| match (*v == F::one(), *c == 0) {
+            (_, true) => format_field(*v),
+            (true, false) => labels.column(*c, l),
+            (false, false) => format!("{} * {}", format_field(*v), labels.column(*c, l)),
+        })
+        .collect();
+    terms.join(" + ")
+}
+
+fn dot<F: PrimeField>(entries: &[(usize, F)], z: &[F]) -> F {
+    entries.iter().map(|(c, v)| *v * z[*c]).sum()
+}
+
+// the values of the non constant columns used by a row
+fn format_values<F: PrimeField>(
+    entries: &[&[(usize, F)]],
+    z: &[F],
+    labels: &Labels,
+    l: usize,
+) -> String {
+    let columns: BTreeSet<usize> = entries
+        .iter()
+        .flat_map(|e| e.iter().map(|(c, _)| *c))
+        .filter(|c| *c != 0)
+        .collect();
+    let values: Vec<String> = columns
+        .into_iter()
+        .map(|c| format!("{} = {}", labels.column(c, l), format_field(z[c])))
+        .collect();
+    values.join(", ")
+}
+
+impl<F: PrimeField> R1CS<F> {
+    /// Row r as a labelled equation with the values it takes on z, e.g.
+    /// `square: (x_0) * (x_0) = (w_0), 3 * 3 = 9 != 10 where x_0 = 3, w_0 = 10`
+    pub fn format_row(&self, r: usize, z: &[F]) -> String {
+        let (a, b, c) = (
+            row_entries(&self.a, r),
+            row_entries(&self.b, r),
+            row_entries(&self.c, r),
+        );
+        let (az, bz, cz) = (dot(&a, z), dot(&b, z), dot(&c, z));
+        format!(
+            "{}: ({}) * ({}) = ({}), {} * {} = {} {} {} where {}",
+            self.labels.row(r),
+            format_lc(&a, &self.labels, self.l),
+            format_lc(&b, &self.labels, self.l),
+            format_lc(&c, &self.labels, self.l),
+            format_field(az),
+            format_field(bz),
+            format_field(az * bz),
+            if az * bz == cz { "==" } else { "!=" },
+            format_field(cz),
+            format_values(&[&a, &b, &c], z, &self.labels, self.l),
+        )
+    }
+
+    /// every failing row of z printed with format_row, empty if z satisfies the R1CS
+    pub fn format_failing_rows(&self, z: &[F]) -> Result<Vec<String>, Error<F>> {
+        match self.is_satisfied(z) {
+            Ok(()) => Ok(Vec::new()),
+            Err(Error::R1CSNotSatisfied(rows)) => {
+                Ok(rows.iter().map(|row| self.format_row(row.row, z)).collect())
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<F: PrimeField> CCS<F> {
+    /// Row r as a labelled equation with the values it takes on z, every M_j * z
+    /// of a term in parentheses, e.g. `row 0: (x_0) * (x_0) + -1 * (w_0) = 0, ...`
+    pub fn format_row(&self, r: usize, z: &[F]) -> String {
+        let rows: Vec<_> = self.m_vec.iter().map(|m_j| row_entries(m_j, r)).collect();
+        let mut equation = String::new();
+        let mut values = String::new();
+        let mut residual = F::zero();
+        for (i, (s, c)) in self.s_vec.iter().zip(&self.v).enumerate() {
+            let sep = if i == 0 { "" } else { " + " };
+            let coefficient = if *c == F::one() {
+                String::new()
+            } else {
+                format!("{} * ", format_field(*c))
+            };
+            let factors: Vec<String> = s
+                .iter()
+                .map(|j| format!("({})", format_lc(&rows[*j], &self.labels, self.l)))
+                .collect();
+            let factor_values: Vec<String> =
+                s.iter().map(|j| format_field(dot(&rows[*j], z))).collect();
+            write!(equation, "{}{}{}", sep, coefficient, factors.join(" * ")).unwrap();
+            write!(
+                values,
+                "{}{}{}",
+                sep,
+                coefficient,
+                factor_values.join(" * ")
+            )
+            .unwrap();
+            residual += *c * s.iter().map(|j| dot(&rows[*j], z)).product::<F>();
+        }
+        let entries: Vec<&[(usize, F)]> = rows.iter().map(|r| &r[..]).collect();
+        format!(
+            "{}: {} = 0, {} = {} where {}",
+            self.labels.row(r),
+            equation,
+            values,
+            format_field(residual),
+            format_values(&entries, z, &self.labels, self.l),
+        )
+    }
+
+    /// every failing row of z printed with format_row, empty if z satisfies the CCS
+    pub fn format_failing_rows(&self, z: &[F]) -> Result<Vec<String>, Error<F>> {
+        match self.is_satisfied(z) {
+            Ok(()) => Ok(Vec::new()),
+            Err(Error::NotSatisfied(rows)) => {
+                Ok(rows.iter().map(|row| self.format_row(row.row, z)).collect())
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_pallas::Fr;
+    use ark_r1cs_std::{alloc::AllocVar, eq::EqGadget, fields::fp::FpVar};
+    use ark_relations::ns;
+    use ark_relations::r1cs::{ConstraintLayer, ConstraintSystem};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+    use crate::ccs::r1cs::tests::{get_test_r1cs, get_test_z};
+
+    #[test]
+    fn test_format_r1cs_row() {
+        let mut r1cs = get_test_r1cs::<Fr>();
+        r1cs.labels.rows.insert(0, "square".to_string());
+        r1cs.labels.columns.insert(1, "x".to_string());
+        let mut z = get_test_z::<Fr>(3);
+        z[3] += Fr::from(1u64);
+        let failing = r1cs.format_failing_rows(&z).unwrap();
+        assert_eq!(
+            failing[0],
+            "square: (x) * (x) = (w_1), 3 * 3 = 9 != 10 where x = 3, w_1 = 10"
+        );
+        assert_eq!(
+            failing[1],
+            "row 1: (w_1) * (x) = (w_2), 10 * 3 = 30 != 27 where x = 3, w_1 = 10, w_2 = 27"
+        );
+        assert!(r1cs.format_failing_rows(&get_test_z(3)).unwrap().is_empty());
+
+        // labels are carried to the CCS
+        let ccs = CCS::<Fr>::from_r1cs(r1cs).unwrap();
+        assert_eq!(
+            ccs.format_failing_rows(&z).unwrap()[0],
+            "square: (x) * (x) + -1 * (w_1) = 0, 3 * 3 + -1 * 10 = -1 where x = 3, w_1 = 10"
+        );
+    }
+
+    #[test]
+    fn test_labels_from_namespaces() {
+        let subscriber = tracing_subscriber::Registry::default().with(ConstraintLayer::default());
+        let (r1cs, z) = tracing::subscriber::with_default(subscriber, || {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let x = FpVar::new_input(ns!(cs, "x"), || Ok(Fr::from(3u64))).unwrap();
+            let y = FpVar::new_witness(ns!(cs, "y"), || Ok(Fr::from(10u64))).unwrap();
+            let square = {
+                let _cs = ns!(cs, "square");
+                &x * &x
+            };
+            {
+                let _cs = ns!(cs, "output");
+                square.enforce_equal(&y).unwrap();
+            }
+            R1CS::from_constraint_system(cs).unwrap()
+        });
+        assert!(r1cs
+            .labels
+            .rows
+            .values()
+            .any(|name| name.contains("square")));
+        let failing = r1cs.format_failing_rows(&z).unwrap();
+        assert_eq!(failing.len(), 1);
+        assert!(failing[0].contains("output"));
+    }
+}
@@ -1,19 +1,84 @@
-use ark_ec::CurveGroup;
-use ark_std::{log2, One, Zero};
-use std::ops::Neg;
+use ark_ff::PrimeField;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+};
+use ark_std::log2;
 use thiserror::Error;
 
+pub mod air;
+pub mod builder;
+pub mod compose;
+pub mod labels;
+pub mod optimize;
+pub mod plonkish;
 pub mod r1cs;
+pub mod random;
+pub mod reduction;
+pub mod stats;
 use r1cs::*;
 
 #[derive(Debug, Error)]
-pub enum Error {
-    #[error("Relation not satisfied")]
-    NotSatisfied,
+pub enum Error<F: PrimeField> {
+    #[error("Relation not satisfied at {} rows{}", .0.len(), first_row(.0.first().map(|r| r.row)))]
+    NotSatisfied(Vec<UnsatisfiedRow<F>>),
+    #[error("R1CS not satisfied at {} rows{}", .0.len(), first_row(.0.first().map(|r| r.row)))]
+    R1CSNotSatisfied(Vec<R1CSUnsatisfiedRow<F>>),
+    #[error(transparent)]
+    Synthesis(#[from] ark_relations::r1cs::SynthesisError),
+    #[error("Expected {0} matrices, got {1}")]
+    InvalidNumMatrices(usize, usize),
+    #[error("Expected {0} multisets, got {1}")]
+    InvalidNumMultisets(usize, usize),
+    #[error("Expected {0} constants, got {1}")]
+    InvalidNumConstants(usize, usize),
+    #[error("Multiset {0} refers to matrix {1}, but there are {2} matrices")]
+    InvalidMultisetIndex(usize, usize, usize),
+    #[error("Multiset {0} has {1} elements, more than the degree {2}")]
+    InvalidDegree(usize, usize, usize),
+    #[error("Matrix {0} is {1}x{2}, expected {3}x{4}")]
+    InvalidMatrixShape(usize, usize, usize, usize, usize),
+    #[error("Matrix {0} has an entry at ({1}, {2}) out of range")]
+    EntryOutOfRange(usize, usize, usize),
+    #[error("s = {0} and s_prime = {1} do not match log2 of the dimensions")]
+    InvalidLogDimensions(usize, usize),
+    #[error("IO length {0} does not fit in {1} columns")]
+    InvalidIOLength(usize, usize),
+    #[error("Expected a vector of length {0}, got {1}")]
+    InvalidVectorLength(usize, usize),
+    #[error("Column {0:?} out of range")]
+    InvalidColumn(plonkish::Column),
+    #[error("Cell ({0}, {1}) out of range")]
+    InvalidCell(usize, usize),
+    #[error("Copy constraint violated at cell ({0}, {1})")]
+    CopyConstraintViolated(usize, usize),
+    #[error("Trace column {0} out of range, width is {1}")]
+    InvalidTraceColumn(usize, usize),
+    #[error("Wire ({0}, {1}) out of range")]
+    InvalidWire(usize, usize),
+    #[error("Row label {0} out of range, there are {1} rows")]
+    InvalidRowLabel(usize, usize),
+    #[error("Column label {0} out of range, there are {1} columns")]
+    InvalidColumnLabel(usize, usize),
+    #[error("Random shape {0:?} needs m > 0, n > l and density > 0")]
+    InvalidRandomShape(random::RandomShape),
+    #[error("Random CCS needs t >= 2 and d >= 1, got t = {0} and d = {1}")]
+    InvalidRandomDegree(usize, usize),
+}
+
+// the variants are public, so the list of failing rows may be empty
+fn first_row(row: Option<usize>) -> String {
+    row.map_or(String::new(), |r| format!(", first failing row {r}"))
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct CCS<C: CurveGroup> {
+pub struct UnsatisfiedRow<F: PrimeField> {
+    pub row: usize,
+    // sum_i c_i * hadamard_{j in S_i} (M_j * z) at this row
+    pub residual: F,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, CanonicalSerialize)]
+pub struct CCS<F: PrimeField> {
     // number of rows in matrix
     pub m: usize,
     // number of cols in matrix, or n = |z|
@@ -32,19 +97,81 @@ pub struct CCS<C: CurveGroup> {
     pub s_prime: usize,
 
     // vector of matrices
-    pub m_vec: Vec<SparseMatrix<C::ScalarField>>,
+    pub m_vec: Vec<SparseMatrix<F>>,
     // vector of multisets
     pub s_vec: Vec<Vec<usize>>,
     // vector of constants
-    pub v: Vec<C::ScalarField>,
+    pub v: Vec<F>,
+    // optional names of the rows and columns, for debugging
+    pub labels: labels::Labels,
 }
 
-impl<C> CCS<C>
-where
-    C: CurveGroup,
-{
-    pub fn from_r1cs(r1cs: R1CS<C::ScalarField>) -> Self {
-        CCS {
+impl<F: PrimeField> CCS<F> {
+    /// Builds a CCS from its matrices, multisets and constants, m and n are taken from
+    /// the first matrix and t, q, d, s, s_prime computed, then the shape is validated.
+    pub fn new(
+        l: usize,
+        m_vec: Vec<SparseMatrix<F>>,
+        s_vec: Vec<Vec<usize>>,
+        v: Vec<F>,
+    ) -> Result<Self, Error<F>> {
+        let (m, n) = m_vec
+            .first()
+            .map(|m_0| (m_0.n_rows, m_0.n_cols))
+            .ok_or(Error::InvalidNumMatrices(1, 0))?;
+        let ccs = CCS {
+            m,
+            n,
+            l,
+            t: m_vec.len(),
+            q: s_vec.len(),
+            d: s_vec.iter().map(|s| s.len()).max().unwrap_or(0),
+            s: log2(m) as usize,
+            s_prime: log2(n) as usize,
+            m_vec,
+            s_vec,
+            v,
+            labels: labels::Labels::default(),
+        };
+        ccs.validate()?;
+        Ok(ccs)
+    }
+
+    /// checks that the sizes and indices are consistent, so is_satisfied can not panic
+    pub fn validate(&self) -> Result<(), Error<F>> {
+        if self.m_vec.len() != self.t {
+            return Err(Error::InvalidNumMatrices(self.t, self.m_vec.len()));
+        }
+        if self.s_vec.len() != self.q {
+            return Err(Error::InvalidNumMultisets(self.q, self.s_vec.len()));
+        }
+        if self.v.len() != self.q {
+            return Err(Error::InvalidNumConstants(self.q, self.v.len()));
+        }
+        for (i, s) in self.s_vec.iter().enumerate() {
+            if s.len() > self.d {
+                return Err(Error::InvalidDegree(i, s.len(), self.d));
+            }
+            if let Some(j) = s.iter().find(|j| **j >= self.t) {
+                return Err(Error::InvalidMultisetIndex(i, *j, self.t));
+            }
+        }
+        for (j, m_j) in self.m_vec.iter().enumerate() {
+            validate_matrix(j, m_j, self.m, self.n)?;
+        }
+        if self.s != log2(self.m) as usize || self.s_prime != log2(self.n) as usize {
+            return Err(Error::InvalidLogDimensions(self.s, self.s_prime));
+        }
+        if self.l >= self.n {
+            return Err(Error::InvalidIOLength(self.l, self.n));
+        }
+        self.labels.validate(self.m, self.n)
+    }
+
+    /// the CCS with terms A * B and -C, fails if the R1CS is not valid
+    pub fn from_r1cs(r1cs: R1CS<F>) -> Result<Self, Error<F>> {
+        r1cs.validate()?;
+        Ok(CCS {
             m: r1cs.a.n_rows,
             n: r1cs.a.n_cols,
             l: r1cs.l,
@@ -54,54 +181,315 @@ where
             s: log2(r1cs.a.n_rows) as usize,
             s_prime: log2(r1cs.a.n_cols) as usize,
             s_vec: vec![vec![0, 1], vec![2]],
-            v: vec![C::ScalarField::one(), C::ScalarField::one().neg()],
+            v: vec![F::one(), F::one().neg()],
             m_vec: vec![r1cs.a, r1cs.b, r1cs.c],
+            labels: r1cs.labels,
+        })
+    }
+
+    /// Pads the matrices to m = 2^s rows and n = 2^s_prime columns, new columns come
+    /// after w so the padded z is z followed by zeros, see pad_z.
+    pub fn pad(&self) -> Self {
+        let m = self.m.next_power_of_two();
+        let n = self.n.next_power_of_two();
+        CCS {
+            m,
+            n,
+            l: self.l,
+            t: self.t,
+            q: self.q,
+            d: self.d,
+            s: log2(m) as usize,
+            s_prime: log2(n) as usize,
+            m_vec: self.m_vec.iter().map(|m_j| m_j.pad(m, n)).collect(),
+            s_vec: self.s_vec.clone(),
+            v: self.v.clone(),
+            labels: self.labels.clone(),
         }
     }
 
-    pub fn is_satisfied(&self, z: &[C::ScalarField]) -> Result<(), Error> {
-        let mut r = vec![C::ScalarField::zero(); self.m];
-        for q_i in 0..self.q {
-            let mut s_set = Vec::new();
-            for s in &self.s_vec[q_i] {
-                // s is multiset
-                s_set.push(&self.m_vec[*s]);
-            }
+    /// extends z = (1, x, w) with zeros to n
+    pub fn pad_z(&self, z: &[F]) -> Vec<F> {
+        pad_vec(z, self.n)
+    }
+
+    /// On failure returns every row with a non zero residual
+    pub fn is_satisfied(&self, z: &[F]) -> Result<(), Error<F>> {
+        self.prepare()?.is_satisfied(z)
+    }
+
+    /// Validates the shape and converts the matrices to CSR once, for callers that
+    /// multiply by them many times
+    pub fn prepare(&self) -> Result<PreparedCCS<'_, F>, Error<F>> {
+        self.validate()?;
+        Ok(PreparedCCS {
+            ccs: self,
+            m_vec: self.m_vec.iter().map(CsrMatrix::from).collect(),
+        })
+    }
+}
+
+/// A CCS with its matrices in CSR form, see CCS::prepare
+#[derive(Clone, Debug)]
+pub struct PreparedCCS<'a, F: PrimeField> {
+    pub ccs: &'a CCS<F>,
+    pub m_vec: Vec<CsrMatrix<F>>,
+}
+
+impl<F: PrimeField> PreparedCCS<'_, F> {
+    /// every M_j * z
+    pub fn mul_vec(&self, z: &[F]) -> Result<Vec<Vec<F>>, Error<F>> {
+        if z.len() != self.ccs.n {
+            return Err(Error::InvalidVectorLength(self.ccs.n, z.len()));
+        }
+        Ok(self.m_vec.iter().map(|m| m.mul_vec(z)).collect())
+    }
+
+    /// On failure returns every row with a non zero residual
+    pub fn is_satisfied(&self, z: &[F]) -> Result<(), Error<F>> {
+        let ccs = self.ccs;
+        let mut r = vec![F::zero(); ccs.m];
+        // every M_j * z is computed once, even if M_j is in several multisets
+        let m_z = self.mul_vec(z)?;
+        for q_i in 0..ccs.q {
             // first each s * z, then hadamard each other in s
-            let hadamard_vec = vec![C::ScalarField::one(); self.m];
-            let s_z_vec: Vec<_> = s_set.iter().map(|s_m| vec_mul_matrix(z, s_m)).collect();
+            let hadamard_vec = vec![F::one(); ccs.m];
+            let s_z_vec: Vec<_> = ccs.s_vec[q_i].iter().map(|s| &m_z[*s]).collect();
             let res = s_z_vec
                 .iter()
                 .fold(hadamard_vec, |acc, x| hadamard(&acc, x));
             // second multiply c
-            let c_s = scalar_mul_vec(self.v[q_i], &res);
+            let c_s = scalar_mul_vec(ccs.v[q_i], &res);
             // third add each other in r
             r = vec_add_vec(&r, &c_s);
         }
-        for e in r {
-            if !e.is_zero() {
-                return Err(Error::NotSatisfied);
-            }
-        }
         //last check sum value = 0
+        let failing: Vec<_> = r
+            .into_iter()
+            .enumerate()
+            .filter(|(_, e)| !e.is_zero())
+            .map(|(row, residual)| UnsatisfiedRow { row, residual })
+            .collect();
+        if !failing.is_empty() {
+            return Err(Error::NotSatisfied(failing));
+        }
         Ok(())
     }
 }
 
+// a deserialized CCS must have a valid shape, see validate, at most MAX_DIMENSION
+// rows and columns and a degree reached by some multiset
+impl<F: PrimeField> Valid for CCS<F> {
+    fn check(&self) -> Result<(), SerializationError> {
+        let max_degree = self.s_vec.iter().map(|s| s.len()).max().unwrap_or(0);
+        if self.m > MAX_DIMENSION || self.n > MAX_DIMENSION || self.d > max_degree {
+            return Err(SerializationError::InvalidData);
+        }
+        self.validate().map_err(|_| SerializationError::InvalidData)
+    }
+}
+
+impl<F: PrimeField> CanonicalDeserialize for CCS<F> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let ccs = CCS {
+            m: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            n: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            l: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            t: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            q: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            d: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            s: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            s_prime: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            m_vec: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            s_vec: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            v: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            labels: labels::Labels::deserialize_with_mode(&mut reader, compress, validate)?,
+        };
+        if validate == Validate::Yes {
+            ccs.check()?;
+        }
+        Ok(ccs)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ccs::builder::tests::get_test_degree_5_ccs;
     use crate::ccs::r1cs::tests::{get_test_r1cs, get_test_z};
-    use ark_pallas::Projective;
+    use ark_pallas::Fr;
+    use ark_std::One;
 
-    pub fn get_test_ccs<C: CurveGroup>() -> CCS<C> {
-        let r1cs = get_test_r1cs::<C::ScalarField>();
-        CCS::from_r1cs(r1cs)
+    pub fn get_test_ccs<F: PrimeField>() -> CCS<F> {
+        let r1cs = get_test_r1cs::<F>();
+        CCS::from_r1cs(r1cs).unwrap()
     }
     #[test]
     fn test_ccs() {
-        let ccs = get_test_ccs::<Projective>();
+        let ccs = get_test_ccs::<Fr>();
         let z = get_test_z(2);
         ccs.is_satisfied(&z).unwrap();
     }
+
+    #[test]
+    fn test_ccs_pad() {
+        let ccs = get_test_ccs::<Fr>().pad();
+        assert_eq!((ccs.m, ccs.n, ccs.s, ccs.s_prime), (4, 8, 2, 3));
+        assert!(ccs.m_vec.iter().all(|m| (m.n_rows, m.n_cols) == (4, 8)));
+        let z = ccs.pad_z(&get_test_z(3));
+        ccs.is_satisfied(&z).unwrap();
+        let mut wrong = z.clone();
+        wrong[3] += Fr::one();
+        assert!(ccs.is_satisfied(&wrong).is_err());
+    }
+
+    #[test]
+    fn test_ccs_validate() {
+        let ccs = get_test_ccs::<Fr>();
+        ccs.validate().unwrap();
+        let rebuilt =
+            CCS::<Fr>::new(ccs.l, ccs.m_vec.clone(), ccs.s_vec.clone(), ccs.v.clone()).unwrap();
+        assert_eq!(rebuilt, ccs);
+
+        let mut bad = ccs.clone();
+        bad.s_vec[1] = vec![3];
+        assert!(matches!(
+            bad.validate(),
+            Err(Error::InvalidMultisetIndex(1, 3, 3))
+        ));
+        let mut bad = ccs.clone();
+        bad.v.pop();
+        assert!(matches!(
+            bad.validate(),
+            Err(Error::InvalidNumConstants(2, 1))
+        ));
+        let mut bad = ccs.clone();
+        bad.l = usize::MAX;
+        assert!(matches!(
+            bad.validate(),
+            Err(Error::InvalidIOLength(usize::MAX, 6))
+        ));
+        let mut bad = ccs.clone();
+        bad.m_vec[2].vals.push((0, 6, Fr::one()));
+        assert!(matches!(
+            bad.validate(),
+            Err(Error::EntryOutOfRange(2, 0, 6))
+        ));
+        let mut bad = ccs.clone();
+        bad.m_vec[0].n_rows = 5;
+        assert!(matches!(
+            bad.is_satisfied(&get_test_z(3)),
+            Err(Error::InvalidMatrixShape(0, 5, 6, 4, 6))
+        ));
+
+        let short = &get_test_z::<Fr>(3)[..5];
+        assert!(matches!(
+            ccs.is_satisfied(short),
+            Err(Error::InvalidVectorLength(6, 5))
+        ));
+
+        let mut r1cs = get_test_r1cs::<Fr>();
+        r1cs.c.vals.push((0, 6, Fr::one()));
+        assert!(matches!(
+            CCS::from_r1cs(r1cs),
+            Err(Error::EntryOutOfRange(2, 0, 6))
+        ));
+    }
+
+    #[test]
+    fn test_ccs_failing_rows() {
+        let ccs = get_test_ccs::<Fr>();
+        let mut z = get_test_z::<Fr>(3);
+        // z1 = x * x is wrong, breaking rows 0 and 1
+        z[3] += Fr::one();
+        match ccs.is_satisfied(&z) {
+            Err(Error::NotSatisfied(rows)) => {
+                let failing: Vec<usize> = rows.iter().map(|r| r.row).collect();
+                assert_eq!(failing, vec![0, 1]);
+                // x * x - z1 = 9 - 10
+                assert_eq!(rows[0].residual, -Fr::one());
+            }
+            _ => panic!("expected failing rows"),
+        }
+        assert_eq!(
+            ccs.is_satisfied(&z).unwrap_err().to_string(),
+            "Relation not satisfied at 2 rows, first failing row 0"
+        );
+        assert_eq!(
+            Error::<Fr>::NotSatisfied(vec![]).to_string(),
+            "Relation not satisfied at 0 rows"
+        );
+        assert_eq!(
+            Error::<Fr>::R1CSNotSatisfied(vec![]).to_string(),
+            "R1CS not satisfied at 0 rows"
+        );
+    }
+
+    #[test]
+    fn test_ccs_serialization() {
+        let ccs = get_test_degree_5_ccs::<Fr>();
+        for compress in [Compress::Yes, Compress::No] {
+            let mut bytes = Vec::new();
+            ccs.serialize_with_mode(&mut bytes, compress).unwrap();
+            let read = CCS::<Fr>::deserialize_with_mode(&bytes[..], compress, Validate::Yes);
+            assert_eq!(read.unwrap(), ccs);
+        }
+
+        // a multiset referring to a missing matrix is rejected
+        let mut bad = ccs.clone();
+        bad.s_vec[0][0] = 3;
+        let mut bytes = Vec::new();
+        bad.serialize_compressed(&mut bytes).unwrap();
+        assert!(CCS::<Fr>::deserialize_compressed(&bytes[..]).is_err());
+        assert_eq!(
+            CCS::<Fr>::deserialize_compressed_unchecked(&bytes[..]).unwrap(),
+            bad
+        );
+        // and so is a truncated one
+        assert!(CCS::<Fr>::deserialize_compressed(&bytes[..bytes.len() - 1]).is_err());
+
+        // a label of a missing column, a degree no multiset reaches and too many rows
+        let mut bad = ccs.clone();
+        bad.labels.columns.insert(ccs.n, "missing".to_string());
+        assert!(matches!(
+            bad.validate(),
+            Err(Error::InvalidColumnLabel(_, _))
+        ));
+        let mut degree = ccs.clone();
+        degree.d = 1 << 40;
+        let huge = CCS::<Fr> {
+            m: 1 << 60,
+            n: 2,
+            l: 0,
+            t: 0,
+            q: 0,
+            d: 0,
+            s: 60,
+            s_prime: 1,
+            m_vec: vec![],
+            s_vec: vec![],
+            v: vec![],
+            labels: labels::Labels::default(),
+        };
+        huge.validate().unwrap();
+        for bad in [bad, degree, huge] {
+            let mut bytes = Vec::new();
+            bad.serialize_compressed(&mut bytes).unwrap();
+            assert!(CCS::<Fr>::deserialize_compressed(&bytes[..]).is_err());
+        }
+
+        // l is not bounded by the dimension limit, it must not overflow the io check
+        let mut bad = ccs.clone();
+        bad.l = u64::MAX as usize;
+        let mut bytes = Vec::new();
+        bad.serialize_compressed(&mut bytes).unwrap();
+        assert!(matches!(
+            CCS::<Fr>::deserialize_compressed(&bytes[..]),
+            Err(SerializationError::InvalidData)
+        ));
+    }
 }
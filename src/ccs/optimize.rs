@@ -0,0 +1,525 @@
+use ark_ff::PrimeField;
+use ark_std::log2;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use super::labels::Labels;
+use super::r1cs::SparseMatrix;
+use super::CCS;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CCSSize {
+    pub m: usize,
+    pub n: usize,
+    pub t: usize,
+    pub q: usize,
+    // number of non zero entries over all the matrices
+    pub nnz: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OptimizationReport {
+    pub before: CCSSize,
+    pub after: CCSSize,
+}
+
+/// Columns of the original z kept by the optimizer, in order. Column 0 and x are
+/// always kept, so the optimized z is still (1, x, w).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnMap {
+    pub columns: Vec<usize>,
+}
+
+impl ColumnMap {
+    /// maps z of the original CCS to z of the optimized one
+    pub fn apply<F: PrimeField>(&self, z: &[F]) -> Vec<F> {
+        self.columns.iter().map(|c| z[*c]).collect()
+    }
+}
+
+// the (column, value) entries of every matrix at one row
+type Row<F> = Vec<Vec<(usize, F)>>;
+
+impl<F: PrimeField> CCS<F> {
+    pub fn size(&self) -> CCSSize {
+        CCSSize {
+            m: self.m,
+            n: self.n,
+            t: self.t,
+            q: self.q,
+            nnz: self.m_vec.iter().map(|m_j| m_j.vals.len()).sum(),
+        }
+    }
+
+    /// Runs the optimization passes: identical matrices are merged and the multisets
+    /// rewritten, then multisets that became identical are merged by adding their
+    /// constants and the matrices of cancelled terms dropped. Rows that are linear
+    /// equalities are solved for a witness column, substituted in the other rows and
+    /// removed, then zero rows and duplicated rows are removed, and finally the
+    /// witness columns no matrix uses. Use the ColumnMap to compute the new z.
+    pub fn optimize(&self) -> (Self, ColumnMap, OptimizationReport) {
+        let ccs = self.dedup_matrices();
+        let ccs = ccs.merge_linear_rows();
+        let ccs = ccs.remove_redundant_rows();
+        let (ccs, map) = ccs.remove_dead_columns();
+        let report = OptimizationReport {
+            before: self.size(),
+            after: ccs.size(),
+        };
+        (ccs, map, report)
+    }
+
+    fn dedup_matrices(&self) -> Self {
+        // terms with the same multiset add up, the order of a multiset does not matter
+        let mut index: HashMap<Vec<(usize, usize, F)>, usize> = HashMap::new();
+        let mut m_vec = Vec::new();
+        let mut terms: Vec<(Vec<usize>, F)> = Vec::new();
+        for (s, c) in self.s_vec.iter().zip(&self.v) {
+            let mut s: Vec<usize> = s
+                .iter()
+                .map(|j| {
+                    let vals = self.m_vec[*j].canonicalize().vals;
+                    *index.entry(vals.clone()).or_insert_with(|| {
+                        m_vec.push(SparseMatrix {
+                            n_rows: self.m,
+                            n_cols: self.n,
+                            vals,
+                        });
+                        m_vec.len() - 1
+                    })
+                })
+                .collect();
+            s.sort();
+            match terms.iter_mut().find(|(t, _)| *t == s) {
+                Some((_, v)) => *v += c,
+                None => terms.push((s, *c)),
+            }
+        }
+        terms.retain(|(_, c)| !c.is_zero());
+        // matrices only used by cancelled terms are dropped
+        let mut used: BTreeMap<usize, usize> = BTreeMap::new();
+        for (s, _) in &terms {
+            for j in s {
+                let next = used.len();
+                used.entry(*j).or_insert(next);
+            }
+        }
+        let mut m_vec: Vec<_> = m_vec
+            .into_iter()
+            .enumerate()
+            .filter(|(j, _)| used.contains_key(j))
+            .map(|(_, m_j)| m_j)
+            .collect();
+        for (s, _) in terms.iter_mut() {
+            s.iter_mut().for_each(|j| *j = used[j]);
+        }
+        if m_vec.is_empty() {
+            m_vec.push(SparseMatrix {
+                n_rows: self.m,
+                n_cols: self.n,
+                vals: Vec::new(),
+            });
+        }
+        let (s_vec, v) = terms.into_iter().unzip();
+        self.with_shape(self.m, self.n, m_vec, s_vec, v, self.labels.clone())
+    }
+
+    // The row as sum_c a_c * z_c with z_0 = 1, or None if a term multiplies two
+    // factors that are not constant at this row. A factor whose row only has column 0
+    // is the constant it holds there, zero for an empty row.
+    fn linear_row(&self, row: &[BTreeMap<usize, F>]) -> Option<BTreeMap<usize, F>> {
+        let mut lin: BTreeMap<usize, F> = BTreeMap::new();
+        for (s, c) in self.s_vec.iter().zip(&self.v) {
+            let mut scale = *c;
+            let mut factors = Vec::new();
+            for j in s {
+                if row[*j].keys().all(|col| *col == 0) {
+                    scale *= row[*j].get(&0).copied().unwrap_or_else(F::zero);
+                } else {
+                    factors.push(&row[*j]);
+                }
+            }
+            match (scale.is_zero(), factors.as_slice()) {
+                (true, _) => {}
+                (false, []) => *lin.entry(0).or_insert_with(F::zero) += scale,
+                (false, [factor]) => {
+                    for (col, v) in factor.iter() {
+                        *lin.entry(*col).or_insert_with(F::zero) += scale * v;
+                    }
+                }
+                (false, _) => return None,
+            }
+        }
+        lin.retain(|_, v| !v.is_zero());
+        Some(lin)
+    }
+
+    // Every row that is a linear equality with a witness column is solved for the one
+    // used the fewest times, which is substituted in every matrix, and removed. Rows
+    // that are always zero are removed too, but the last row is kept as a constant
+    // term would fail on an empty row.
+    fn merge_linear_rows(&self) -> Self {
+        let mut rows: Vec<Vec<BTreeMap<usize, F>>> = vec![vec![BTreeMap::new(); self.t]; self.m];
+        // the (row, matrix) pairs with an entry in each column
+        let mut uses: Vec<BTreeSet<(usize, usize)>> = vec![BTreeSet::new(); self.n];
+        for (j, m_j) in self.m_vec.iter().enumerate() {
+            for (r, c, v) in &m_j.vals {
+                *rows[*r][j].entry(*c).or_insert_with(F::zero) += v;
+                uses[*c].insert((*r, j));
+            }
+        }
+        let mut removed = vec![false; self.m];
+        let mut remaining = self.m;
+        for r in 0..self.m {
+            let lin = match self.linear_row(&rows[r]) {
+                Some(lin) => lin,
+                None => continue,
+            };
+            let pivot = lin
+                .keys()
+                .filter(|c| **c > self.l)
+                .min_by_key(|c| (uses[**c].len(), **c));
+            // a constraint on the constant and the public inputs only stays
+            if remaining == 1 || (pivot.is_none() && !lin.is_empty()) {
+                continue;
+            }
+            removed[r] = true;
+            remaining -= 1;
+            for (j, entries) in rows[r].iter_mut().enumerate() {
+                for c in std::mem::take(entries).into_keys() {
+                    uses[c].remove(&(r, j));
+                }
+            }
+            let w = match pivot {
+                Some(w) => *w,
+                None => continue,
+            };
+            // z_w = sum_{c != w} -a_c / a_w * z_c
+            let inv = -lin[&w].inverse().unwrap();
+            for (r_2, j) in std::mem::take(&mut uses[w]) {
+                let v_w = rows[r_2][j].remove(&w).unwrap();
+                for (c, a_c) in lin.iter().filter(|(c, _)| **c != w) {
+                    let entry = rows[r_2][j].entry(*c).or_insert_with(F::zero);
+                    *entry += v_w * a_c * inv;
+                    if entry.is_zero() {
+                        rows[r_2][j].remove(c);
+                        uses[*c].remove(&(r_2, j));
+                    } else {
+                        uses[*c].insert((r_2, j));
+                    }
+                }
+            }
+        }
+
+        let kept: Vec<usize> = (0..self.m).filter(|r| !removed[*r]).collect();
+        let mut m_vec: Vec<SparseMatrix<F>> = (0..self.t)
+            .map(|_| SparseMatrix {
+                n_rows: kept.len(),
+                n_cols: self.n,
+                vals: Vec::new(),
+            })
+            .collect();
+        let mut new_row = vec![None; self.m];
+        for (i, r) in kept.iter().enumerate() {
+            new_row[*r] = Some(i);
+            for (j, entries) in rows[*r].iter().enumerate() {
+                m_vec[j]
+                    .vals
+                    .extend(entries.iter().map(|(c, v)| (i, *c, *v)));
+            }
+        }
+        let labels = self.labels.remap(|r| new_row[r], Some);
+        self.with_shape(
+            kept.len(),
+            self.n,
+            m_vec,
+            self.s_vec.clone(),
+            self.v.clone(),
+            labels,
+        )
+    }
+
+    fn remove_redundant_rows(&self) -> Self {
+        let mut rows: Vec<Row<F>> = vec![vec![Vec::new(); self.t]; self.m];
+        for (j, m_j) in self.m_vec.iter().enumerate() {
+            for (r, c, v) in &m_j.vals {
+                rows[*r][j].push((*c, *v));
+            }
+        }
+        // a constant term makes a zero row fail, one of them must stay
+        let constant = self
+            .s_vec
+            .iter()
+            .zip(&self.v)
+            .any(|(s, c)| s.is_empty() && !c.is_zero());
+        let mut seen = HashSet::new();
+        let mut kept: Vec<usize> = (0..self.m)
+            .filter(|r| {
+                (constant || rows[*r].iter().any(|row| !row.is_empty())) && seen.insert(&rows[*r])
+            })
+            .collect();
+        if kept.is_empty() {
+            kept.push(0);
+        }
+        let mut new_row = vec![None; self.m];
+        for (i, r) in kept.iter().enumerate() {
+            new_row[*r] = Some(i);
+        }
+        let m_vec = self
+            .m_vec
+            .iter()
+            .map(|m_j| SparseMatrix {
+                n_rows: kept.len(),
+                n_cols: self.n,
+                vals: m_j
+                    .vals
+                    .iter()
+                    .filter_map(|(r, c, v)| new_row[*r].map(|r| (r, *c, *v)))
+                    .collect(),
+            })
+            .collect();
+        let labels = self.labels.remap(|r| new_row[r], Some);
+        self.with_shape(
+            kept.len(),
+            self.n,
+            m_vec,
+            self.s_vec.clone(),
+            self.v.clone(),
+            labels,
+        )
+    }
+
+    fn remove_dead_columns(&self) -> (Self, ColumnMap) {
+        let mut used = vec![false; self.n];
+        used[..=self.l].iter_mut().for_each(|u| *u = true);
+        for (_, c, _) in self.m_vec.iter().flat_map(|m_j| &m_j.vals) {
+            used[*c] = true;
+        }
+        let columns: Vec<usize> = (0..self.n).filter(|c| used[*c]).collect();
+        let mut new_col = vec![None; self.n];
+        for (i, c) in columns.iter().enumerate() {
+            new_col[*c] = Some(i);
+        }
+        let m_vec = self
+            .m_vec
+            .iter()
+            .map(|m_j| SparseMatrix {
+                n_rows: self.m,
+                n_cols: columns.len(),
+                vals: m_j
+                    .vals
+                    .iter()
+                    .map(|(r, c, v)| (*r, new_col[*c].unwrap(), *v))
+                    .collect(),
+            })
+            .collect();
+        let labels = self.labels.remap(Some, |c| new_col[c]);
+        let ccs = self.with_shape(
+            self.m,
+            columns.len(),
+            m_vec,
+            self.s_vec.clone(),
+            self.v.clone(),
+            labels,
+        );
+        (ccs, ColumnMap { columns })
+    }
+
+    // unlike new, keeps m and n when there are no multisets left
+    fn with_shape(
+        &self,
+        m: usize,
+        n: usize,
+        m_vec: Vec<SparseMatrix<F>>,
+        s_vec: Vec<Vec<usize>>,
+        v: Vec<F>,
+        labels: Labels,
+    ) -> Self {
+        CCS {
+            m,
+            n,
+            l: self.l,
+            t: m_vec.len(),
+            q: s_vec.len(),
+            d: s_vec.iter().map(|s| s.len()).max().unwrap_or(0),
+            s: log2(m) as usize,
+            s_prime: log2(n) as usize,
+            m_vec,
+            s_vec,
+            v,
+            labels,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_pallas::Fr;
+    use ark_std::One;
+
+    use super::*;
+    use crate::ccs::builder::tests::get_test_degree_5_ccs;
+    use crate::ccs::r1cs::tests::{get_test_r1cs, get_test_z};
+    use crate::ccs::r1cs::to_f_vec;
+
+    // the test R1CS with a copy of A, the term A * B split in three, row 0 repeated,
+    // a zero row and an unused witness column inserted at 3
+    fn get_redundant_ccs() -> CCS<Fr> {
+        let ccs = CCS::<Fr>::from_r1cs(get_test_r1cs()).unwrap();
+        let (m, n) = (ccs.m + 2, ccs.n + 1);
+        let mut m_vec: Vec<SparseMatrix<Fr>> = ccs
+            .m_vec
+            .iter()
+            .map(|m_j| {
+                let shift = |c: usize| if c >= 3 { c + 1 } else { c };
+                let mut vals: Vec<_> = m_j
+                    .vals
+                    .iter()
+                    .map(|(r, c, v)| (*r, shift(*c), *v))
+                    .collect();
+                let row_0: Vec<_> = vals
+                    .iter()
+                    .filter(|(r, _, _)| *r == 0)
+                    .map(|(_, c, v)| (4, *c, *v))
+                    .collect();
+                vals.extend(row_0);
+                SparseMatrix {
+                    n_rows: m,
+                    n_cols: n,
+                    vals,
+                }
+            })
+            .collect();
+        m_vec.push(m_vec[0].clone());
+        let two = Fr::from(2u64);
+        CCS::new(
+            ccs.l,
+            m_vec,
+            vec![vec![0, 1], vec![2], vec![1, 3], vec![3, 1]],
+            vec![Fr::one(), -Fr::one(), two, -two],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_optimize() {
+        let ccs = get_redundant_ccs();
+        let mut z = get_test_z::<Fr>(3);
+        z.insert(3, Fr::from(7u64));
+        ccs.is_satisfied(&z).unwrap();
+
+        let (optimized, map, report) = ccs.optimize();
+        assert_eq!(
+            report.before,
+            CCSSize {
+                m: 6,
+                n: 7,
+                t: 4,
+                q: 4,
+                nnz: 24
+            }
+        );
+        // (z2 + x) * 1 = z3 and (z3 + 5) * 1 = y are solved for z2 and y, leaving
+        // x * x = z1 and z1 * x = z3 - x over (1, x, z1, z3)
+        assert_eq!(
+            report.after,
+            CCSSize {
+                m: 2,
+                n: 4,
+                t: 3,
+                q: 2,
+                nnz: 7
+            }
+        );
+        assert_eq!(optimized.s_vec, vec![vec![0, 1], vec![2]]);
+        assert_eq!(map.columns, vec![0, 1, 4, 6]);
+        assert_eq!(map.apply(&z), to_f_vec::<Fr>(vec![1, 3, 9, 30]));
+        optimized.validate().unwrap();
+        optimized.is_satisfied(&map.apply(&z)).unwrap();
+
+        let mut wrong = z.clone();
+        wrong[4] += Fr::one();
+        assert!(optimized.is_satisfied(&map.apply(&wrong)).is_err());
+    }
+
+    #[test]
+    fn test_optimize_without_linear_rows_is_unchanged() {
+        let ccs = get_test_degree_5_ccs::<Fr>();
+        let (optimized, map, report) = ccs.optimize();
+        assert_eq!(report.before, report.after);
+        assert_eq!(map.columns, (0..ccs.n).collect::<Vec<_>>());
+        assert_eq!(optimized, ccs);
+    }
+
+    #[test]
+    fn test_optimize_linear_rows() {
+        // the rows multiplied by the constant 1 are linear, even with x public
+        let mut r1cs = get_test_r1cs::<Fr>();
+        r1cs.l = 2;
+        let ccs = CCS::<Fr>::from_r1cs(r1cs).unwrap();
+        let (optimized, map, _) = ccs.optimize();
+        assert_eq!((optimized.m, optimized.n), (2, 4));
+        assert_eq!(map.columns, vec![0, 1, 2, 3]);
+        let z = get_test_z::<Fr>(3);
+        optimized.is_satisfied(&map.apply(&z)).unwrap();
+        let mut wrong = z.clone();
+        wrong[2] += Fr::one();
+        assert!(optimized.is_satisfied(&map.apply(&wrong)).is_err());
+    }
+
+    #[test]
+    fn test_optimize_constant_term() {
+        // x - 5 = 0 at row 0 and the constant -5 alone at the zero row 1, which no
+        // z satisfies
+        let ccs = CCS::<Fr>::new(
+            1,
+            vec![SparseMatrix {
+                n_rows: 2,
+                n_cols: 2,
+                vals: vec![(0, 1, Fr::one())],
+            }],
+            vec![vec![0], vec![]],
+            vec![Fr::one(), -Fr::from(5u64)],
+        )
+        .unwrap();
+        let z = to_f_vec::<Fr>(vec![1, 5]);
+        assert!(ccs.is_satisfied(&z).is_err());
+        let (optimized, map, _) = ccs.optimize();
+        assert_eq!(optimized.m, 2);
+        assert!(optimized.is_satisfied(&map.apply(&z)).is_err());
+        assert!(ccs.remove_redundant_rows().is_satisfied(&z).is_err());
+
+        // the last row is not solved away, the constant would fail on an empty CCS
+        let ccs = CCS::<Fr>::new(
+            0,
+            vec![SparseMatrix {
+                n_rows: 1,
+                n_cols: 2,
+                vals: vec![(0, 1, Fr::one())],
+            }],
+            vec![vec![0], vec![]],
+            vec![Fr::one(), -Fr::from(5u64)],
+        )
+        .unwrap();
+        let (optimized, map, _) = ccs.optimize();
+        assert_eq!(optimized.m, 1);
+        optimized.is_satisfied(&map.apply(&z)).unwrap();
+    }
+
+    #[test]
+    fn test_optimize_prunes_cancelled_matrices() {
+        // A * B - C plus 2 * D - 2 * D, D only used by the cancelled terms
+        let mut ccs = CCS::<Fr>::from_r1cs(get_test_r1cs()).unwrap();
+        let mut d = ccs.m_vec[0].clone();
+        d.vals.push((0, 5, Fr::one()));
+        ccs.m_vec.push(d);
+        ccs.s_vec.extend([vec![3], vec![3]]);
+        ccs.v.extend([Fr::from(2u64), -Fr::from(2u64)]);
+        (ccs.t, ccs.q) = (4, 4);
+        ccs.validate().unwrap();
+
+        let deduped = ccs.dedup_matrices();
+        assert_eq!((deduped.t, deduped.q), (3, 2));
+        assert_eq!(deduped.m_vec, ccs.m_vec[..3]);
+        deduped.is_satisfied(&get_test_z(3)).unwrap();
+    }
+}
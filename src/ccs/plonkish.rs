@@ -0,0 +1,372 @@
+use ark_ff::PrimeField;
+use std::collections::{HashMap, HashSet};
+
+use super::r1cs::SparseMatrix;
+use super::{Error, CCS};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Column {
+    Advice(usize),
+    Fixed(usize),
+}
+
+/// sum_i c_i * prod_{col in S_i} col, evaluated at every row
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Gate<F: PrimeField> {
+    pub terms: Vec<(F, Vec<Column>)>,
+}
+
+/// Plonkish table: advice columns hold the witness, fixed columns (selectors included)
+/// are part of the circuit. Cells are (advice column, row).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Plonkish<F: PrimeField> {
+    pub num_rows: usize,
+    pub num_advice: usize,
+    // fixed[j][i] is fixed column j at row i
+    pub fixed: Vec<Vec<F>>,
+    pub gates: Vec<Gate<F>>,
+    pub copy_constraints: Vec<((usize, usize), (usize, usize))>,
+    // public cells, in the order of x
+    pub public: Vec<(usize, usize)>,
+}
+
+impl<F: PrimeField> Plonkish<F> {
+    pub fn validate(&self) -> Result<(), Error<F>> {
+        if let Some(f) = self.fixed.iter().find(|f| f.len() != self.num_rows) {
+            return Err(Error::InvalidVectorLength(self.num_rows, f.len()));
+        }
+        for gate in &self.gates {
+            for column in gate.terms.iter().flat_map(|(_, s)| s) {
+                match column {
+                    Column::Advice(j) if *j >= self.num_advice => {
+                        return Err(Error::InvalidColumn(*column))
+                    }
+                    Column::Fixed(j) if *j >= self.fixed.len() => {
+                        return Err(Error::InvalidColumn(*column))
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let cells = self
+            .copy_constraints
+            .iter()
+            .flat_map(|(a, b)| [a, b])
+            .chain(&self.public);
+        for (j, i) in cells {
+            if *j >= self.num_advice || *i >= self.num_rows {
+                return Err(Error::InvalidCell(*j, *i));
+            }
+        }
+        Ok(())
+    }
+
+    fn cell(&self, (j, i): (usize, usize)) -> usize {
+        j * self.num_rows + i
+    }
+
+    /// Index in z = (1, x, w) of every advice cell and the length of z. Cells linked
+    /// by copy constraints share a variable, public cells come first.
+    fn variables(&self) -> (Vec<usize>, usize) {
+        let num_cells = self.num_advice * self.num_rows;
+        // union-find over the cells
+        let mut parent: Vec<usize> = (0..num_cells).collect();
+        fn find(parent: &mut [usize], mut c: usize) -> usize {
+            while parent[c] != c {
+                parent[c] = parent[parent[c]];
+                c = parent[c];
+            }
+            c
+        }
+        for (a, b) in &self.copy_constraints {
+            let root_a = find(&mut parent, self.cell(*a));
+            let root_b = find(&mut parent, self.cell(*b));
+            parent[root_a] = root_b;
+        }
+
+        let mut index: HashMap<usize, usize> = HashMap::new();
+        let mut next = 1;
+        let roots: Vec<usize> = self
+            .public
+            .iter()
+            .map(|c| self.cell(*c))
+            .chain(0..num_cells)
+            .map(|c| find(&mut parent, c))
+            .collect();
+        for root in roots {
+            index.entry(root).or_insert_with(|| {
+                next += 1;
+                next - 1
+            });
+        }
+        let vars = (0..num_cells)
+            .map(|c| index[&find(&mut parent, c)])
+            .collect();
+        (vars, next)
+    }
+
+    /// public cells are deduplicated by the copy constraints
+    pub fn num_public(&self) -> usize {
+        let (vars, _) = self.variables();
+        let public: HashSet<usize> = self.public.iter().map(|c| vars[self.cell(*c)]).collect();
+        public.len()
+    }
+
+    /// z = (1, x, w) from the advice columns, advice[j][i] being column j at row i.
+    /// Fails if cells linked by a copy constraint hold different values.
+    pub fn assignment_to_z(&self, advice: &[Vec<F>]) -> Result<Vec<F>, Error<F>> {
+        self.validate()?;
+        if advice.len() != self.num_advice {
+            return Err(Error::InvalidVectorLength(self.num_advice, advice.len()));
+        }
+        let (vars, n) = self.variables();
+        let mut z: Vec<Option<F>> = vec![None; n];
+        z[0] = Some(F::one());
+        for (j, column) in advice.iter().enumerate() {
+            if column.len() != self.num_rows {
+                return Err(Error::InvalidVectorLength(self.num_rows, column.len()));
+            }
+            for (i, value) in column.iter().enumerate() {
+                let var = &mut z[vars[self.cell((j, i))]];
+                match var {
+                    Some(v) if v != value => return Err(Error::CopyConstraintViolated(j, i)),
+                    _ => *var = Some(*value),
+                }
+            }
+        }
+        Ok(z.into_iter().map(|v| v.unwrap()).collect())
+    }
+}
+
+impl<F: PrimeField> CCS<F> {
+    /// Plonkish to CCS as in the CCS paper: every (gate, column) pair becomes a matrix
+    /// selecting the advice cell at each row, or putting the fixed value in column 0,
+    /// and every gate term a multiset. Gate k is checked in the k-th block of num_rows
+    /// rows, its matrices are zero elsewhere, so a constant term gets its own matrix
+    /// with a one in column 0 at the rows of its block.
+    pub fn from_plonkish(plonkish: &Plonkish<F>) -> Result<Self, Error<F>> {
+        plonkish.validate()?;
+        let (vars, n) = plonkish.variables();
+        let num_rows = plonkish.num_rows;
+        let m = num_rows * plonkish.gates.len().max(1);
+
+        let mut m_vec = Vec::new();
+        let mut s_vec = Vec::new();
+        let mut v = Vec::new();
+        for (k, gate) in plonkish.gates.iter().enumerate() {
+            // None is the constant one
+            let mut matrices: HashMap<Option<Column>, usize> = HashMap::new();
+            for (c, columns) in &gate.terms {
+                let columns: Vec<Option<Column>> = if columns.is_empty() {
+                    vec![None]
+                } else {
+                    columns.iter().copied().map(Some).collect()
+                };
+                let s = columns
+                    .into_iter()
+                    .map(|column| {
+                        *matrices.entry(column).or_insert_with(|| {
+                            let vals = (0..num_rows)
+                                .map(|i| match column {
+                                    Some(Column::Advice(j)) => {
+                                        (k * num_rows + i, vars[plonkish.cell((j, i))], F::one())
+                                    }
+                                    Some(Column::Fixed(j)) => {
+                                        (k * num_rows + i, 0, plonkish.fixed[j][i])
+                                    }
+                                    None => (k * num_rows + i, 0, F::one()),
+                                })
+                                .filter(|(_, _, v)| !v.is_zero())
+                                .collect();
+                            m_vec.push(SparseMatrix {
+                                n_rows: m,
+                                n_cols: n,
+                                vals,
+                            });
+                            m_vec.len() - 1
+                        })
+                    })
+                    .collect();
+                s_vec.push(s);
+                v.push(*c);
+            }
+        }
+        if m_vec.is_empty() {
+            // no gates, a single zero matrix keeps the dimensions
+            m_vec.push(SparseMatrix {
+                n_rows: m,
+                n_cols: n,
+                vals: Vec::new(),
+            });
+        }
+        CCS::new(plonkish.num_public(), m_vec, s_vec, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_pallas::Fr;
+    use ark_std::One;
+
+    use super::*;
+    use crate::ccs::r1cs::to_f_vec;
+
+    // advice a, b, c and fixed q_l, q_r, q_o, q_m, q_c with the vanilla Plonk gate
+    // q_l * a + q_r * b + q_o * c + q_m * a * b + q_c = 0, proving x^3 + x + 5 = y
+    fn get_test_plonkish() -> Plonkish<Fr> {
+        let (a, b, c) = (Column::Advice(0), Column::Advice(1), Column::Advice(2));
+        let mut fixed: Vec<Vec<Fr>> = vec![
+            vec![0, 0, 1, 1],
+            vec![0, 0, 1, 0],
+            vec![0, 0, 0, 0],
+            vec![1, 1, 0, 0],
+            vec![0, 0, 0, 5],
+        ]
+        .into_iter()
+        .map(to_f_vec)
+        .collect();
+        // q_o = -1 on every row
+        fixed[2] = vec![-Fr::one(); 4];
+        let gate = Gate {
+            terms: vec![
+                (Fr::one(), vec![Column::Fixed(0), a]),
+                (Fr::one(), vec![Column::Fixed(1), b]),
+                (Fr::one(), vec![Column::Fixed(2), c]),
+                (Fr::one(), vec![Column::Fixed(3), a, b]),
+                (Fr::one(), vec![Column::Fixed(4)]),
+            ],
+        };
+        Plonkish {
+            num_rows: 4,
+            num_advice: 3,
+            fixed,
+            gates: vec![gate],
+            // x * x = x2, x2 * x = x3, x3 + x = t, t + 5 = y
+            copy_constraints: vec![
+                ((0, 0), (1, 0)),
+                ((1, 0), (1, 1)),
+                ((1, 1), (1, 2)),
+                ((2, 0), (0, 1)),
+                ((2, 1), (0, 2)),
+                ((2, 2), (0, 3)),
+            ],
+            public: vec![(0, 0), (2, 3)],
+        }
+    }
+
+    fn get_test_advice(x: usize) -> Vec<Vec<Fr>> {
+        let (x2, x3) = (x * x, x * x * x);
+        vec![
+            to_f_vec(vec![x, x2, x3, x3 + x]),
+            to_f_vec(vec![x, x, x, 0]),
+            to_f_vec(vec![x2, x3, x3 + x, x3 + x + 5]),
+        ]
+    }
+
+    #[test]
+    fn test_from_plonkish() {
+        let plonkish = get_test_plonkish();
+        let ccs = CCS::<Fr>::from_plonkish(&plonkish).unwrap();
+        // q_m * a * b has degree 3
+        assert_eq!((ccs.m, ccs.l, ccs.t, ccs.q, ccs.d), (4, 2, 8, 5, 3));
+
+        let z = plonkish.assignment_to_z(&get_test_advice(3)).unwrap();
+        assert_eq!(z[1..3].to_vec(), to_f_vec::<Fr>(vec![3, 35]));
+        ccs.is_satisfied(&z).unwrap();
+
+        // consistent copies but a wrong output
+        let mut advice = get_test_advice(3);
+        advice[2][3] += Fr::one();
+        let z = plonkish.assignment_to_z(&advice).unwrap();
+        assert!(ccs.is_satisfied(&z).is_err());
+
+        // x3 copied into the next row with another value, detected at its source cell
+        let mut advice = get_test_advice(3);
+        advice[0][2] += Fr::one();
+        assert!(matches!(
+            plonkish.assignment_to_z(&advice),
+            Err(Error::CopyConstraintViolated(2, 1))
+        ));
+    }
+
+    #[test]
+    fn test_from_plonkish_two_gates() {
+        // gate 0: a * b = c, gate 1: s * (c - a) = 0 with selector s
+        let (a, b, c) = (Column::Advice(0), Column::Advice(1), Column::Advice(2));
+        let mul = Gate {
+            terms: vec![(Fr::one(), vec![a, b]), (-Fr::one(), vec![c])],
+        };
+        let eq = Gate {
+            terms: vec![
+                (Fr::one(), vec![Column::Fixed(0), c]),
+                (-Fr::one(), vec![Column::Fixed(0), a]),
+            ],
+        };
+        let plonkish = Plonkish {
+            num_rows: 2,
+            num_advice: 3,
+            fixed: vec![to_f_vec(vec![0, 1])],
+            gates: vec![mul, eq],
+            copy_constraints: vec![],
+            public: vec![(2, 0)],
+        };
+        let ccs = CCS::<Fr>::from_plonkish(&plonkish).unwrap();
+        assert_eq!(ccs.m, 4);
+
+        let advice = vec![
+            to_f_vec(vec![2, 1]),
+            to_f_vec(vec![3, 1]),
+            to_f_vec(vec![6, 1]),
+        ];
+        ccs.is_satisfied(&plonkish.assignment_to_z(&advice).unwrap())
+            .unwrap();
+        // 2 * 1 = 2 but the selector enforces c = a on row 1
+        let advice = vec![
+            to_f_vec(vec![2, 1]),
+            to_f_vec(vec![3, 2]),
+            to_f_vec(vec![6, 2]),
+        ];
+        assert!(ccs
+            .is_satisfied(&plonkish.assignment_to_z(&advice).unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn test_from_plonkish_constant_term() {
+        // gate 0: a * b = c, gate 1: a - 2 = 0, the constant only holds in its block
+        let (a, b, c) = (Column::Advice(0), Column::Advice(1), Column::Advice(2));
+        let mul = Gate {
+            terms: vec![(Fr::one(), vec![a, b]), (-Fr::one(), vec![c])],
+        };
+        let two = Gate {
+            terms: vec![(Fr::one(), vec![a]), (-Fr::from(2u64), vec![])],
+        };
+        let plonkish = Plonkish {
+            num_rows: 2,
+            num_advice: 3,
+            fixed: vec![],
+            gates: vec![mul, two],
+            copy_constraints: vec![],
+            public: vec![],
+        };
+        let ccs = CCS::<Fr>::from_plonkish(&plonkish).unwrap();
+        assert_eq!((ccs.m, ccs.t, ccs.q), (4, 5, 4));
+
+        let advice = vec![
+            to_f_vec(vec![2, 2]),
+            to_f_vec(vec![3, 5]),
+            to_f_vec(vec![6, 10]),
+        ];
+        ccs.is_satisfied(&plonkish.assignment_to_z(&advice).unwrap())
+            .unwrap();
+        let advice = vec![
+            to_f_vec(vec![2, 3]),
+            to_f_vec(vec![3, 5]),
+            to_f_vec(vec![6, 15]),
+        ];
+        assert!(matches!(
+            ccs.is_satisfied(&plonkish.assignment_to_z(&advice).unwrap()),
+            Err(Error::NotSatisfied(rows)) if rows.len() == 1 && rows[0].row == 3
+        ));
+    }
+}
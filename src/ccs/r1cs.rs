@@ -1,6 +1,18 @@
 use ark_ff::PrimeField;
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::ops::{Add, Mul, Sub};
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+use super::labels::Labels;
+use super::Error;
+use crate::utils::mle::eq_evals;
+
+#[derive(Clone, Debug, Eq, PartialEq, CanonicalSerialize)]
 pub struct SparseMatrix<F: PrimeField> {
     //number of rows
     pub n_rows: usize,
@@ -10,6 +22,169 @@ pub struct SparseMatrix<F: PrimeField> {
     pub vals: Vec<(usize, usize, F)>,
 }
 
+// matrix j must be n_rows x n_cols with every entry inside
+pub(crate) fn validate_matrix<F: PrimeField>(
+    j: usize,
+    m: &SparseMatrix<F>,
+    n_rows: usize,
+    n_cols: usize,
+) -> Result<(), Error<F>> {
+    if m.n_rows != n_rows || m.n_cols != n_cols {
+        return Err(Error::InvalidMatrixShape(
+            j, m.n_rows, m.n_cols, n_rows, n_cols,
+        ));
+    }
+    if let Some((r, c, _)) = m.vals.iter().find(|(r, c, _)| *r >= n_rows || *c >= n_cols) {
+        return Err(Error::EntryOutOfRange(j, *r, *c));
+    }
+    Ok(())
+}
+
+/// the largest number of rows or columns accepted when deserializing, so that a
+/// few bytes can not claim a matrix too large to multiply by
+pub const MAX_DIMENSION: usize = 1 << 30;
+
+// a deserialized matrix must be at most MAX_DIMENSION square with every entry inside
+impl<F: PrimeField> Valid for SparseMatrix<F> {
+    fn check(&self) -> Result<(), SerializationError> {
+        if self.n_rows > MAX_DIMENSION || self.n_cols > MAX_DIMENSION {
+            return Err(SerializationError::InvalidData);
+        }
+        validate_matrix(0, self, self.n_rows, self.n_cols)
+            .map_err(|_| SerializationError::InvalidData)
+    }
+}
+
+impl<F: PrimeField> CanonicalDeserialize for SparseMatrix<F> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let m = SparseMatrix {
+            n_rows: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            n_cols: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            vals: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+        };
+        if validate == Validate::Yes {
+            m.check()?;
+        }
+        Ok(m)
+    }
+}
+
+impl<F: PrimeField> SparseMatrix<F> {
+    /// the same entries in a larger zero matrix
+    pub fn pad(&self, n_rows: usize, n_cols: usize) -> Self {
+        assert!(n_rows >= self.n_rows && n_cols >= self.n_cols);
+        SparseMatrix {
+            n_rows,
+            n_cols,
+            vals: self.vals.clone(),
+        }
+    }
+
+    pub fn transpose(&self) -> Self {
+        SparseMatrix {
+            n_rows: self.n_cols,
+            n_cols: self.n_rows,
+            vals: self.vals.iter().map(|(r, c, v)| (*c, *r, *v)).collect(),
+        }
+    }
+
+    /// M^T * v without building the transpose, v has n_rows entries
+    pub fn transpose_mul_vec(&self, v: &[F]) -> Vec<F> {
+        assert_eq!(v.len(), self.n_rows);
+        let mut res = vec![F::zero(); self.n_cols];
+        for (r, c, m) in &self.vals {
+            res[*c] += *m * v[*r];
+        }
+        res
+    }
+
+    pub fn scale(&self, c: F) -> Self {
+        SparseMatrix {
+            n_rows: self.n_rows,
+            n_cols: self.n_cols,
+            vals: self.vals.iter().map(|(r, j, v)| (*r, *j, c * v)).collect(),
+        }
+    }
+
+    /// [self | other], both with the same number of rows
+    pub fn hstack(&self, other: &Self) -> Self {
+        assert_eq!(self.n_rows, other.n_rows);
+        let mut vals = self.vals.clone();
+        vals.extend(other.vals.iter().map(|(r, c, v)| (*r, self.n_cols + c, *v)));
+        SparseMatrix {
+            n_rows: self.n_rows,
+            n_cols: self.n_cols + other.n_cols,
+            vals,
+        }
+    }
+
+    /// self above other, both with the same number of columns
+    pub fn vstack(&self, other: &Self) -> Self {
+        assert_eq!(self.n_cols, other.n_cols);
+        let mut vals = self.vals.clone();
+        vals.extend(other.vals.iter().map(|(r, c, v)| (self.n_rows + r, *c, *v)));
+        SparseMatrix {
+            n_rows: self.n_rows + other.n_rows,
+            n_cols: self.n_cols,
+            vals,
+        }
+    }
+
+    /// entries sorted by row then column, duplicates added and zeros removed, so
+    /// equal matrices have equal vals
+    pub fn canonicalize(&self) -> Self {
+        let mut entries: BTreeMap<(usize, usize), F> = BTreeMap::new();
+        for (r, c, v) in &self.vals {
+            *entries.entry((*r, *c)).or_insert_with(F::zero) += v;
+        }
+        SparseMatrix {
+            n_rows: self.n_rows,
+            n_cols: self.n_cols,
+            vals: entries
+                .into_iter()
+                .filter(|(_, v)| !v.is_zero())
+                .map(|((r, c), v)| (r, c, v))
+                .collect(),
+        }
+    }
+
+    /// M~(r_x, r_y) = sum_{i, j} M[i][j] * eq(r_x, i) * eq(r_y, j), with 2^|r_x| rows
+    /// and 2^|r_y| columns at least
+    pub fn evaluate_mle(&self, r_x: &[F], r_y: &[F]) -> F {
+        assert!(self.n_rows <= 1 << r_x.len() && self.n_cols <= 1 << r_y.len());
+        let (eq_x, eq_y) = (eq_evals(r_x), eq_evals(r_y));
+        self.vals
+            .iter()
+            .map(|(r, c, v)| *v * eq_x[*r] * eq_y[*c])
+            .sum()
+    }
+}
+
+/// entrywise sum, both matrices must have the same dimensions
+impl<F: PrimeField> Add for &SparseMatrix<F> {
+    type Output = SparseMatrix<F>;
+    fn add(self, other: Self) -> SparseMatrix<F> {
+        assert_eq!((self.n_rows, self.n_cols), (other.n_rows, other.n_cols));
+        SparseMatrix {
+            n_rows: self.n_rows,
+            n_cols: self.n_cols,
+            vals: [self.vals.clone(), other.vals.clone()].concat(),
+        }
+        .canonicalize()
+    }
+}
+
+pub fn pad_vec<F: PrimeField>(v: &[F], len: usize) -> Vec<F> {
+    assert!(len >= v.len());
+    let mut v = v.to_vec();
+    v.resize(len, F::zero());
+    v
+}
+
 pub fn dense_matrix_to_sparse<F: PrimeField>(m: Vec<Vec<F>>) -> SparseMatrix<F> {
     let mut sm = SparseMatrix::<F> {
         n_rows: m.len(),
@@ -47,12 +222,67 @@ pub fn to_f_matrix<F: PrimeField>(m: Vec<Vec<usize>>) -> Vec<Vec<F>> {
     f_m
 }
 
-pub fn vec_mul_matrix<F: PrimeField>(z: &[F], m: &SparseMatrix<F>) -> Vec<F> {
-    let mut v = vec![F::zero(); m.n_rows];
-    for (i, j, val) in &m.vals {
-        v[*i] += *val * z[*j];
+// compressed sparse row form, the entries of row i are row_ptr[i]..row_ptr[i + 1]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CsrMatrix<F: PrimeField> {
+    pub n_rows: usize,
+    pub n_cols: usize,
+    pub row_ptr: Vec<usize>,
+    pub col_idx: Vec<usize>,
+    pub vals: Vec<F>,
+}
+
+impl<F: PrimeField> From<&SparseMatrix<F>> for CsrMatrix<F> {
+    // counting sort by row, entries keep their order within a row
+    fn from(m: &SparseMatrix<F>) -> Self {
+        let mut row_ptr = vec![0; m.n_rows + 1];
+        for (i, _, _) in &m.vals {
+            row_ptr[*i + 1] += 1;
+        }
+        for i in 0..m.n_rows {
+            row_ptr[i + 1] += row_ptr[i];
+        }
+        let mut next = row_ptr.clone();
+        let mut col_idx = vec![0; m.vals.len()];
+        let mut vals = vec![F::zero(); m.vals.len()];
+        for (i, j, v) in &m.vals {
+            col_idx[next[*i]] = *j;
+            vals[next[*i]] = *v;
+            next[*i] += 1;
+        }
+        CsrMatrix {
+            n_rows: m.n_rows,
+            n_cols: m.n_cols,
+            row_ptr,
+            col_idx,
+            vals,
+        }
+    }
+}
+
+impl<F: PrimeField> CsrMatrix<F> {
+    /// M * z, rows are computed in parallel with the `parallel` feature
+    pub fn mul_vec(&self, z: &[F]) -> Vec<F> {
+        let row = |i: usize| {
+            (self.row_ptr[i]..self.row_ptr[i + 1])
+                .map(|k| self.vals[k] * z[self.col_idx[k]])
+                .sum()
+        };
+        #[cfg(feature = "parallel")]
+        return (0..self.n_rows).into_par_iter().map(row).collect();
+        #[cfg(not(feature = "parallel"))]
+        (0..self.n_rows).map(row).collect()
     }
-    v
+}
+
+// converts m on every call, callers multiplying by the same matrix several times
+// should convert once with CsrMatrix::from
+pub fn vec_mul_matrix<F: PrimeField>(z: &[F], m: &SparseMatrix<F>) -> Result<Vec<F>, Error<F>> {
+    validate_matrix(0, m, m.n_rows, m.n_cols)?;
+    if z.len() != m.n_cols {
+        return Err(Error::InvalidVectorLength(m.n_cols, z.len()));
+    }
+    Ok(CsrMatrix::from(m).mul_vec(z))
 }
 
 pub fn hadamard<F: PrimeField>(a: &[F], b: &[F]) -> Vec<F> {
@@ -71,12 +301,25 @@ pub fn vec_sub_vec<F: PrimeField>(v1: &[F], v2: &[F]) -> Vec<F> {
     v1.iter().zip(v2.iter()).map(|(v1, v2)| *v1 - v2).collect()
 }
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct R1CSUnsatisfiedRow<F: PrimeField> {
+    pub row: usize,
+    pub az: F,
+    pub bz: F,
+    pub cz: F,
+    // az * bz - u * cz - e
+    pub residual: F,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, CanonicalSerialize)]
 pub struct R1CS<F: PrimeField> {
     //io length
     pub l: usize,
     pub a: SparseMatrix<F>,
     pub b: SparseMatrix<F>,
     pub c: SparseMatrix<F>,
+    // optional names of the rows and columns, for debugging
+    pub labels: Labels,
 }
 
 impl<F: PrimeField> R1CS<F> {
@@ -85,11 +328,383 @@ impl<F: PrimeField> R1CS<F> {
     pub fn split_z(&self, z: &[F]) -> (Vec<F>, Vec<F>) {
         (z[self.l + 1..].to_vec(), z[1..self.l + 1].to_vec())
     }
+
+    /// Builds an R1CS and checks its shape
+    pub fn new(
+        l: usize,
+        a: SparseMatrix<F>,
+        b: SparseMatrix<F>,
+        c: SparseMatrix<F>,
+    ) -> Result<Self, Error<F>> {
+        let r1cs = R1CS {
+            l,
+            a,
+            b,
+            c,
+            labels: Labels::default(),
+        };
+        r1cs.validate()?;
+        Ok(r1cs)
+    }
+
+    /// checks that A, B and C have the same dimensions, entries in range and that
+    /// the io fits in z = (1, x, w)
+    pub fn validate(&self) -> Result<(), Error<F>> {
+        let (n_rows, n_cols) = (self.a.n_rows, self.a.n_cols);
+        for (j, m) in [&self.a, &self.b, &self.c].into_iter().enumerate() {
+            validate_matrix(j, m, n_rows, n_cols)?;
+        }
+        if self.l >= n_cols {
+            return Err(Error::InvalidIOLength(self.l, n_cols));
+        }
+        self.labels.validate(n_rows, n_cols)
+    }
+
+    /// Az o Bz = Cz, on failure returns every failing row with its Az, Bz and Cz
+    pub fn is_satisfied(&self, z: &[F]) -> Result<(), Error<F>> {
+        self.is_relaxed_satisfied(z, F::one(), &vec![F::zero(); self.a.n_rows])
+    }
+
+    /// Az o Bz = u * Cz + e
+    pub fn is_relaxed_satisfied(&self, z: &[F], u: F, e: &[F]) -> Result<(), Error<F>> {
+        self.prepare()?.is_relaxed_satisfied(z, u, e)
+    }
+
+    /// Validates the shape and converts A, B and C to CSR once, for callers that
+    /// multiply by them many times
+    pub fn prepare(&self) -> Result<PreparedR1CS<'_, F>, Error<F>> {
+        self.validate()?;
+        Ok(PreparedR1CS {
+            r1cs: self,
+            a: CsrMatrix::from(&self.a),
+            b: CsrMatrix::from(&self.b),
+            c: CsrMatrix::from(&self.c),
+        })
+    }
+
+    /// Pads the matrices with zero rows and columns up to powers of two. New columns
+    /// come after w, so the padded z is z followed by zeros, see pad_z.
+    pub fn pad(&self) -> Self {
+        let n_rows = self.a.n_rows.next_power_of_two();
+        let n_cols = self.a.n_cols.next_power_of_two();
+        R1CS {
+            l: self.l,
+            a: self.a.pad(n_rows, n_cols),
+            b: self.b.pad(n_rows, n_cols),
+            c: self.c.pad(n_rows, n_cols),
+            labels: self.labels.clone(),
+        }
+    }
+
+    /// extends z = (1, x, w) with zeros to the number of columns
+    pub fn pad_z(&self, z: &[F]) -> Vec<F> {
+        pad_vec(z, self.a.n_cols)
+    }
+
+    /// Finalizes an arkworks constraint system and returns its R1CS together with
+    /// z = (1, x, w). The cs must have been synthesized with witness generation.
+    /// Rows are labelled with their namespaces when constraints were traced with
+    /// a ConstraintLayer.
+    pub fn from_constraint_system(cs: ConstraintSystemRef<F>) -> Result<(Self, Vec<F>), Error<F>> {
+        cs.finalize();
+        let rows = cs
+            .constraint_names()
+            .unwrap_or_default()
+            .into_iter()
+            .enumerate()
+            .filter(|(_, name)| !name.is_empty())
+            .collect();
+        let matrices = cs.to_matrices().ok_or(SynthesisError::MissingCS)?;
+        let cs = cs.borrow().ok_or(SynthesisError::MissingCS)?;
+        // arkworks already orders variables as (1, instance, witness)
+        let n_cols = matrices.num_instance_variables + matrices.num_witness_variables;
+        if cs.instance_assignment.len() != matrices.num_instance_variables
+            || cs.witness_assignment.len() != matrices.num_witness_variables
+        {
+            return Err(SynthesisError::AssignmentMissing.into());
+        }
+        let to_sparse = |m: Vec<Vec<(F, usize)>>| SparseMatrix {
+            n_rows: matrices.num_constraints,
+            n_cols,
+            vals: m
+                .into_iter()
+                .enumerate()
+                .flat_map(|(i, row)| row.into_iter().map(move |(v, j)| (i, j, v)))
+                .collect(),
+        };
+
+        let r1cs = R1CS {
+            l: matrices.num_instance_variables - 1,
+            a: to_sparse(matrices.a),
+            b: to_sparse(matrices.b),
+            c: to_sparse(matrices.c),
+            labels: Labels {
+                rows,
+                columns: BTreeMap::new(),
+            },
+        };
+        let z = [
+            cs.instance_assignment.clone(),
+            cs.witness_assignment.clone(),
+        ]
+        .concat();
+        Ok((r1cs, z))
+    }
+}
+
+/// An R1CS with its matrices in CSR form, see R1CS::prepare
+#[derive(Clone, Debug)]
+pub struct PreparedR1CS<'a, F: PrimeField> {
+    pub r1cs: &'a R1CS<F>,
+    pub a: CsrMatrix<F>,
+    pub b: CsrMatrix<F>,
+    pub c: CsrMatrix<F>,
+}
+
+impl<F: PrimeField> PreparedR1CS<'_, F> {
+    /// Az o Bz = u * Cz + e
+    pub fn is_relaxed_satisfied(&self, z: &[F], u: F, e: &[F]) -> Result<(), Error<F>> {
+        if z.len() != self.a.n_cols {
+            return Err(Error::InvalidVectorLength(self.a.n_cols, z.len()));
+        }
+        if e.len() != self.a.n_rows {
+            return Err(Error::InvalidVectorLength(self.a.n_rows, e.len()));
+        }
+        let az = self.a.mul_vec(z);
+        let bz = self.b.mul_vec(z);
+        let cz = self.c.mul_vec(z);
+        let failing: Vec<_> = (0..self.a.n_rows)
+            .map(|row| R1CSUnsatisfiedRow {
+                row,
+                az: az[row],
+                bz: bz[row],
+                cz: cz[row],
+                residual: az[row] * bz[row] - u * cz[row] - e[row],
+            })
+            .filter(|r| !r.residual.is_zero())
+            .collect();
+        if !failing.is_empty() {
+            return Err(Error::R1CSNotSatisfied(failing));
+        }
+        Ok(())
+    }
+}
+
+// a deserialized R1CS must have a valid shape, see validate
+impl<F: PrimeField> Valid for R1CS<F> {
+    fn check(&self) -> Result<(), SerializationError> {
+        self.validate().map_err(|_| SerializationError::InvalidData)
+    }
+}
+
+impl<F: PrimeField> CanonicalDeserialize for R1CS<F> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let r1cs = R1CS {
+            l: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            a: SparseMatrix::deserialize_with_mode(&mut reader, compress, validate)?,
+            b: SparseMatrix::deserialize_with_mode(&mut reader, compress, validate)?,
+            c: SparseMatrix::deserialize_with_mode(&mut reader, compress, validate)?,
+            labels: Labels::deserialize_with_mode(&mut reader, compress, validate)?,
+        };
+        if validate == Validate::Yes {
+            r1cs.check()?;
+        }
+        Ok(r1cs)
+    }
+}
+
+/// A variable of the R1CSBuilder, inputs go to x and witnesses to w
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Variable {
+    One,
+    Input(usize),
+    Witness(usize),
+}
+
+/// sum_i c_i * var_i
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct LinearCombination<F: PrimeField>(pub Vec<(F, Variable)>);
+
+impl<F: PrimeField> LinearCombination<F> {
+    pub fn constant(c: F) -> Self {
+        LinearCombination(vec![(c, Variable::One)])
+    }
+}
+
+impl<F: PrimeField> From<Variable> for LinearCombination<F> {
+    fn from(v: Variable) -> Self {
+        LinearCombination(vec![(F::one(), v)])
+    }
+}
+
+impl<F: PrimeField, T: Into<LinearCombination<F>>> Add<T> for LinearCombination<F> {
+    type Output = Self;
+    fn add(mut self, other: T) -> Self {
+        self.0.extend(other.into().0);
+        self
+    }
+}
+
+impl<F: PrimeField, T: Into<LinearCombination<F>>> Sub<T> for LinearCombination<F> {
+    type Output = Self;
+    fn sub(mut self, other: T) -> Self {
+        self.0
+            .extend(other.into().0.into_iter().map(|(c, v)| (-c, v)));
+        self
+    }
+}
+
+impl<F: PrimeField> Mul<F> for LinearCombination<F> {
+    type Output = Self;
+    fn mul(self, c: F) -> Self {
+        LinearCombination(self.0.into_iter().map(|(c_i, v)| (c * c_i, v)).collect())
+    }
+}
+
+/// Native R1CS construction with witness generation: variables are allocated with
+/// their values, and build returns the R1CS with z = (1, x, w) satisfying it.
+#[derive(Clone, Debug, Default)]
+pub struct R1CSBuilder<F: PrimeField> {
+    inputs: Vec<F>,
+    witness: Vec<F>,
+    constraints: Vec<[LinearCombination<F>; 3]>,
+    variable_labels: BTreeMap<Variable, String>,
+    constraint_labels: BTreeMap<usize, String>,
+}
+
+impl<F: PrimeField> R1CSBuilder<F> {
+    pub fn new() -> Self {
+        R1CSBuilder {
+            inputs: Vec::new(),
+            witness: Vec::new(),
+            constraints: Vec::new(),
+            variable_labels: BTreeMap::new(),
+            constraint_labels: BTreeMap::new(),
+        }
+    }
+
+    /// names the column of v in the built R1CS
+    pub fn label_variable(&mut self, v: Variable, name: impl Into<String>) {
+        self.variable_labels.insert(v, name.into());
+    }
+
+    /// names the row of the last enforced constraint
+    pub fn label_constraint(&mut self, name: impl Into<String>) {
+        if let Some(r) = self.constraints.len().checked_sub(1) {
+            self.constraint_labels.insert(r, name.into());
+        }
+    }
+
+    pub fn alloc_input(&mut self, value: F) -> Variable {
+        self.inputs.push(value);
+        Variable::Input(self.inputs.len() - 1)
+    }
+
+    pub fn alloc_witness(&mut self, value: F) -> Variable {
+        self.witness.push(value);
+        Variable::Witness(self.witness.len() - 1)
+    }
+
+    /// value of a linear combination under the current assignment
+    pub fn value(&self, lc: &LinearCombination<F>) -> F {
+        lc.0.iter()
+            .map(|(c, v)| {
+                *c * match v {
+                    Variable::One => F::one(),
+                    Variable::Input(i) => self.inputs[*i],
+                    Variable::Witness(i) => self.witness[*i],
+                }
+            })
+            .sum()
+    }
+
+    /// a * b = c
+    pub fn enforce(
+        &mut self,
+        a: impl Into<LinearCombination<F>>,
+        b: impl Into<LinearCombination<F>>,
+        c: impl Into<LinearCombination<F>>,
+    ) {
+        self.constraints.push([a.into(), b.into(), c.into()]);
+    }
+
+    /// allocates the witness a * b and enforces it
+    pub fn mul(
+        &mut self,
+        a: impl Into<LinearCombination<F>>,
+        b: impl Into<LinearCombination<F>>,
+    ) -> Variable {
+        let (a, b) = (a.into(), b.into());
+        let product = self.alloc_witness(self.value(&a) * self.value(&b));
+        self.enforce(a, b, product);
+        product
+    }
+
+    /// (a - b) * 1 = 0
+    pub fn enforce_eq(
+        &mut self,
+        a: impl Into<LinearCombination<F>>,
+        b: impl Into<LinearCombination<F>>,
+    ) {
+        self.enforce(a.into() - b, Variable::One, LinearCombination::default());
+    }
+
+    /// The R1CS with one row per constraint, terms of the same variable are added,
+    /// together with z = (1, x, w). z only satisfies it if the allocated values do.
+    pub fn build(self) -> (R1CS<F>, Vec<F>) {
+        let l = self.inputs.len();
+        let n_cols = 1 + l + self.witness.len();
+        let column = |v: &Variable| match v {
+            Variable::One => 0,
+            Variable::Input(i) => 1 + i,
+            Variable::Witness(i) => 1 + l + i,
+        };
+        let mut matrices: [SparseMatrix<F>; 3] = core::array::from_fn(|_| SparseMatrix {
+            n_rows: self.constraints.len(),
+            n_cols,
+            vals: Vec::new(),
+        });
+        for (i, constraint) in self.constraints.iter().enumerate() {
+            for (m, lc) in matrices.iter_mut().zip(constraint) {
+                let mut row: BTreeMap<usize, F> = BTreeMap::new();
+                for (c, v) in &lc.0 {
+                    *row.entry(column(v)).or_insert_with(F::zero) += c;
+                }
+                m.vals.extend(
+                    row.into_iter()
+                        .filter(|(_, c)| !c.is_zero())
+                        .map(|(j, c)| (i, j, c)),
+                );
+            }
+        }
+        let [a, b, c] = matrices;
+        let z = [vec![F::one()], self.inputs, self.witness].concat();
+        let labels = Labels {
+            rows: self.constraint_labels,
+            columns: self
+                .variable_labels
+                .iter()
+                .map(|(v, name)| (column(v), name.clone()))
+                .collect(),
+        };
+        let r1cs = R1CS { l, a, b, c, labels };
+        (r1cs, z)
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use crate::ccs::CCS;
+    use ark_pallas::Fr;
+    use ark_r1cs_std::{alloc::AllocVar, eq::EqGadget, fields::fp::FpVar};
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use ark_std::{One, UniformRand, Zero};
+
     pub fn get_test_r1cs<F: PrimeField>() -> R1CS<F> {
         //R1CS: x^3 + x + 5 = y)
         // x * x = z1
@@ -117,7 +732,13 @@ pub mod tests {
             vec![0, 0, 1, 0, 0, 0],
         ]));
 
-        R1CS::<F> { l: 1, a, b, c }
+        R1CS::<F> {
+            l: 1,
+            a,
+            b,
+            c,
+            labels: Labels::default(),
+        }
     }
 
     //z = (1,x,w)
@@ -131,4 +752,285 @@ pub mod tests {
             input * input * input + input,
         ])
     }
+
+    // x^3 + x + 5 = y with x and y public
+    pub struct CubicCircuit<F: PrimeField> {
+        pub x: F,
+    }
+
+    impl<F: PrimeField> ConstraintSynthesizer<F> for CubicCircuit<F> {
+        fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+            let y = self.x * self.x * self.x + self.x + F::from(5u64);
+            let x = FpVar::new_input(cs.clone(), || Ok(self.x))?;
+            let y = FpVar::new_input(cs, || Ok(y))?;
+            let x3 = &x * &x * &x;
+            (x3 + &x + FpVar::Constant(F::from(5u64))).enforce_equal(&y)
+        }
+    }
+
+    pub fn get_test_r1cs_from_circuit<F: PrimeField>(x: usize) -> (R1CS<F>, Vec<F>) {
+        let cs = ConstraintSystem::<F>::new_ref();
+        CubicCircuit {
+            x: F::from(x as u64),
+        }
+        .generate_constraints(cs.clone())
+        .unwrap();
+        R1CS::from_constraint_system(cs).unwrap()
+    }
+
+    #[test]
+    fn test_from_constraint_system() {
+        let (r1cs, z) = get_test_r1cs_from_circuit::<ark_pallas::Fr>(3);
+        assert_eq!(r1cs.l, 2);
+        assert_eq!(z.len(), r1cs.a.n_cols);
+        let (_, x) = r1cs.split_z(&z);
+        assert_eq!(x, to_f_vec(vec![3, 35]));
+
+        let ccs = CCS::<Fr>::from_r1cs(r1cs).unwrap();
+        ccs.is_satisfied(&z).unwrap();
+        let mut wrong = z.clone();
+        wrong[2] += ark_pallas::Fr::from(1u64);
+        assert!(ccs.is_satisfied(&wrong).is_err());
+    }
+
+    #[test]
+    fn test_r1cs_failing_rows() {
+        use ark_pallas::Fr;
+
+        let r1cs = get_test_r1cs::<Fr>();
+        r1cs.is_satisfied(&get_test_z(3)).unwrap();
+        let mut z = get_test_z::<Fr>(3);
+        // y = 36 instead of 35 only breaks the last row
+        z[2] += Fr::from(1u64);
+        match r1cs.is_satisfied(&z) {
+            Err(Error::R1CSNotSatisfied(rows)) => {
+                assert_eq!(rows.len(), 1);
+                let row = &rows[0];
+                assert_eq!(row.row, 3);
+                assert_eq!(row.az, Fr::from(35u64));
+                assert_eq!(row.bz, Fr::from(1u64));
+                assert_eq!(row.cz, Fr::from(36u64));
+                assert_eq!(row.residual, -Fr::from(1u64));
+            }
+            _ => panic!("expected failing rows"),
+        }
+    }
+
+    #[test]
+    fn test_r1cs_validate() {
+        use ark_pallas::Fr;
+
+        let r1cs = get_test_r1cs::<Fr>();
+        let R1CS { l, a, b, c, .. } = r1cs.clone();
+        assert_eq!(R1CS::new(l, a.clone(), b.clone(), c.clone()).unwrap(), r1cs);
+
+        let mut short = b.clone();
+        short.n_cols = 5;
+        assert!(matches!(
+            R1CS::new(l, a.clone(), short, c.clone()),
+            Err(Error::InvalidMatrixShape(1, 4, 5, 4, 6))
+        ));
+        assert!(matches!(
+            R1CS::new(6, a.clone(), b.clone(), c.clone()),
+            Err(Error::InvalidIOLength(6, 6))
+        ));
+        assert!(matches!(
+            R1CS::new(usize::MAX, a, b, c),
+            Err(Error::InvalidIOLength(usize::MAX, 6))
+        ));
+        assert!(matches!(
+            r1cs.is_satisfied(&get_test_z::<Fr>(3)[..4]),
+            Err(Error::InvalidVectorLength(6, 4))
+        ));
+    }
+
+    #[test]
+    fn test_csr_mul_vec() {
+        use ark_pallas::Fr;
+        use ark_std::UniformRand;
+
+        let mut rng = ark_std::test_rng();
+        let (n_rows, n_cols) = (13, 9);
+        // unsorted, with repeated positions
+        let vals = (0..40)
+            .map(|k| ((k * 7) % n_rows, (k * 5) % n_cols, Fr::rand(&mut rng)))
+            .collect();
+        let m = SparseMatrix {
+            n_rows,
+            n_cols,
+            vals,
+        };
+        let z: Vec<Fr> = (0..n_cols).map(|_| Fr::rand(&mut rng)).collect();
+
+        let mut expected = vec![Fr::from(0u64); n_rows];
+        for (i, j, v) in &m.vals {
+            expected[*i] += *v * z[*j];
+        }
+        let csr = CsrMatrix::from(&m);
+        assert_eq!(csr.row_ptr.len(), n_rows + 1);
+        assert_eq!(csr.mul_vec(&z), expected);
+        assert_eq!(vec_mul_matrix(&z, &m).unwrap(), expected);
+        assert!(matches!(
+            vec_mul_matrix(&z[1..], &m),
+            Err(Error::InvalidVectorLength(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_r1cs_pad() {
+        let r1cs = get_test_r1cs::<ark_pallas::Fr>().pad();
+        assert_eq!((r1cs.a.n_rows, r1cs.a.n_cols), (4, 8));
+        let z = r1cs.pad_z(&get_test_z(3));
+        assert_eq!(z.len(), 8);
+        let (w, x) = r1cs.split_z(&z);
+        assert_eq!((w.len(), x.len()), (6, 1));
+        CCS::<Fr>::from_r1cs(r1cs)
+            .unwrap()
+            .is_satisfied(&z)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_r1cs_builder() {
+        // get_test_r1cs written with the builder, y is a witness as there
+        let mut builder = R1CSBuilder::<Fr>::new();
+        let x = builder.alloc_input(Fr::from(3u64));
+        let y = builder.alloc_witness(Fr::from(35u64));
+        let z1 = builder.mul(x, x);
+        let z2 = builder.mul(z1, x);
+        let sum = LinearCombination::from(x) + z2;
+        let z3 = builder.alloc_witness(builder.value(&sum));
+        builder.enforce(sum, Variable::One, z3);
+        let five = LinearCombination::constant(Fr::from(5u64));
+        builder.enforce(five + z3, Variable::One, y);
+        let (r1cs, z) = builder.build();
+        assert_eq!(r1cs, get_test_r1cs::<Fr>());
+        assert_eq!(z, get_test_z(3));
+        r1cs.is_satisfied(&z).unwrap();
+
+        // a wrong claimed output is only caught by the R1CS
+        let mut builder = R1CSBuilder::<Fr>::new();
+        let x = builder.alloc_input(Fr::from(3u64));
+        let y = builder.alloc_input(Fr::from(10u64));
+        let x2 = builder.mul(x, x);
+        builder.enforce_eq(LinearCombination::from(x2) * Fr::from(2u64) - x, y);
+        builder.label_constraint("output");
+        builder.label_variable(y, "y");
+        let (r1cs, z) = builder.build();
+        assert_eq!(r1cs.l, 2);
+        assert_eq!(
+            (r1cs.labels.row(1), r1cs.labels.column(2, 2)),
+            ("output".to_string(), "y".to_string())
+        );
+        match r1cs.is_satisfied(&z) {
+            Err(Error::R1CSNotSatisfied(rows)) => assert_eq!(rows[0].row, 1),
+            _ => panic!("expected row 1 to fail"),
+        }
+    }
+
+    #[test]
+    fn test_sparse_matrix_algebra() {
+        let a = get_test_r1cs::<Fr>().a;
+        let dense = |m: &SparseMatrix<Fr>| {
+            let mut d = vec![vec![Fr::zero(); m.n_cols]; m.n_rows];
+            for (r, c, v) in &m.vals {
+                d[*r][*c] += v;
+            }
+            d
+        };
+
+        let t = a.transpose();
+        assert_eq!((t.n_rows, t.n_cols), (6, 4));
+        assert_eq!(t.transpose(), a);
+        let v: Vec<Fr> = to_f_vec(vec![1, 2, 3, 4]);
+        assert_eq!(a.transpose_mul_vec(&v), CsrMatrix::from(&t).mul_vec(&v));
+
+        let two = Fr::from(2u64);
+        assert_eq!(&a + &a, a.scale(two));
+        // a - a cancels every entry
+        assert!((&a + &a.scale(-Fr::one())).vals.is_empty());
+
+        let h = a.hstack(&a.scale(two));
+        let v = a.vstack(&t.transpose());
+        assert_eq!((h.n_rows, h.n_cols, v.n_rows, v.n_cols), (4, 12, 8, 6));
+        assert_eq!(dense(&h)[2][10], two);
+        assert_eq!(dense(&v)[6], dense(&a)[2]);
+
+        let mut shuffled = a.clone();
+        shuffled.vals.reverse();
+        shuffled.vals.push((0, 1, Fr::zero()));
+        assert_eq!(shuffled.canonicalize(), a);
+
+        // on the boolean hypercube the MLE is the matrix
+        let bits = |i: usize, n: usize| -> Vec<Fr> {
+            (0..n).map(|k| Fr::from(((i >> k) & 1) as u64)).collect()
+        };
+        let d = dense(&a);
+        for (i, row) in d.iter().enumerate() {
+            for (j, m) in row.iter().enumerate() {
+                assert_eq!(a.evaluate_mle(&bits(i, 2), &bits(j, 3)), *m);
+            }
+        }
+        // and it is linear in the matrix
+        let mut rng = ark_std::test_rng();
+        let r_x: Vec<Fr> = (0..2).map(|_| Fr::rand(&mut rng)).collect();
+        let r_y: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        assert_eq!(
+            (&a + &a.scale(two)).evaluate_mle(&r_x, &r_y),
+            a.evaluate_mle(&r_x, &r_y) * Fr::from(3u64)
+        );
+    }
+
+    #[test]
+    fn test_r1cs_serialization() {
+        let mut r1cs = get_test_r1cs::<Fr>();
+        r1cs.labels.rows.insert(0, "square".to_string());
+        for compress in [Compress::Yes, Compress::No] {
+            let mut bytes = Vec::new();
+            r1cs.serialize_with_mode(&mut bytes, compress).unwrap();
+            assert_eq!(bytes.len(), r1cs.serialized_size(compress));
+            let read = R1CS::<Fr>::deserialize_with_mode(&bytes[..], compress, Validate::Yes);
+            assert_eq!(read.unwrap(), r1cs);
+        }
+
+        // an entry out of range is only accepted unchecked
+        let mut bad = r1cs.clone();
+        bad.b.vals.push((0, 6, Fr::one()));
+        let mut bytes = Vec::new();
+        bad.serialize_compressed(&mut bytes).unwrap();
+        assert!(R1CS::<Fr>::deserialize_compressed(&bytes[..]).is_err());
+        assert_eq!(
+            R1CS::<Fr>::deserialize_compressed_unchecked(&bytes[..]).unwrap(),
+            bad
+        );
+        let mut bytes = Vec::new();
+        bad.b.serialize_uncompressed(&mut bytes).unwrap();
+        assert!(SparseMatrix::<Fr>::deserialize_uncompressed(&bytes[..]).is_err());
+
+        // so are a label of a missing row and a huge matrix without entries
+        let mut bad = r1cs.clone();
+        bad.labels.rows.insert(4, "missing".to_string());
+        assert!(matches!(bad.validate(), Err(Error::InvalidRowLabel(4, 4))));
+        let mut bytes = Vec::new();
+        bad.serialize_compressed(&mut bytes).unwrap();
+        assert!(R1CS::<Fr>::deserialize_compressed(&bytes[..]).is_err());
+        let huge = SparseMatrix::<Fr> {
+            n_rows: 1 << 60,
+            n_cols: 1,
+            vals: vec![],
+        };
+        let mut bytes = Vec::new();
+        huge.serialize_compressed(&mut bytes).unwrap();
+        assert!(SparseMatrix::<Fr>::deserialize_compressed(&bytes[..]).is_err());
+
+        // l is not bounded by the dimension limit, it must not overflow the io check
+        let mut bad = r1cs.clone();
+        bad.l = u64::MAX as usize;
+        let mut bytes = Vec::new();
+        bad.serialize_compressed(&mut bytes).unwrap();
+        assert!(matches!(
+            R1CS::<Fr>::deserialize_compressed(&bytes[..]),
+            Err(SerializationError::InvalidData)
+        ));
+    }
 }
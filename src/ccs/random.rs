@@ -0,0 +1,342 @@
+use ark_ff::PrimeField;
+use ark_std::rand::Rng;
+use std::collections::BTreeMap;
+
+use super::r1cs::{hadamard, vec_add_vec, CsrMatrix, SparseMatrix, R1CS};
+use super::{Error, CCS};
+
+/// Dimensions of a random shape, density is the fraction of non zero entries in
+/// each row of each matrix, at least one per row.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RandomShape {
+    pub m: usize,
+    pub n: usize,
+    pub l: usize,
+    pub density: f64,
+}
+
+impl RandomShape {
+    /// fails unless m > 0, n > l and density > 0
+    pub fn validate<F: PrimeField>(&self) -> Result<(), Error<F>> {
+        if self.m == 0 || self.n <= self.l || self.density.is_nan() || self.density <= 0.0 {
+            return Err(Error::InvalidRandomShape(*self));
+        }
+        Ok(())
+    }
+
+    // k distinct columns, sampled with Floyd's algorithm
+    fn row<F: PrimeField, R: Rng>(&self, rng: &mut R) -> BTreeMap<usize, F> {
+        let k = ((self.density * self.n as f64).round() as usize).clamp(1, self.n);
+        let mut row = BTreeMap::new();
+        for j in self.n - k..self.n {
+            let c = rng.gen_range(0..=j);
+            let c = if row.contains_key(&c) { j } else { c };
+            row.insert(c, F::rand(rng));
+        }
+        row
+    }
+
+    // rows are sorted, so the matrix is canonical
+    fn matrix<F: PrimeField, R: Rng>(&self, rng: &mut R) -> SparseMatrix<F> {
+        SparseMatrix {
+            n_rows: self.m,
+            n_cols: self.n,
+            vals: (0..self.m)
+                .flat_map(|r| {
+                    self.row(rng)
+                        .into_iter()
+                        .map(move |(c, v)| (r, c, v))
+                        .collect::<Vec<_>>()
+                })
+                .collect(),
+        }
+    }
+
+    // a random row with M_r * z = target, one of its entries is solved for
+    fn solved_row<F: PrimeField, R: Rng>(
+        &self,
+        rng: &mut R,
+        z: &[F],
+        target: F,
+    ) -> BTreeMap<usize, F> {
+        let mut row = self.row(rng);
+        // z_0 = 1 can always be solved for
+        let col = match rng.gen_range(0..self.n) {
+            c if z[c].is_zero() => 0,
+            c => c,
+        };
+        row.remove(&col);
+        let rest: F = row.iter().map(|(c, v)| *v * z[*c]).sum();
+        row.insert(col, (target - rest) * z[col].inverse().unwrap());
+        row.retain(|_, v| !v.is_zero());
+        row
+    }
+
+    fn z<F: PrimeField, R: Rng>(&self, rng: &mut R) -> Result<Vec<F>, Error<F>> {
+        self.validate()?;
+        let mut z: Vec<F> = (0..self.n).map(|_| F::rand(rng)).collect();
+        z[0] = F::one();
+        Ok(z)
+    }
+}
+
+impl<F: PrimeField> R1CS<F> {
+    /// Random R1CS with random A and B, and C solved row by row so that the returned
+    /// z = (1, x, w) satisfies it. Use a seeded rng to reproduce a shape.
+    pub fn random<R: Rng>(rng: &mut R, shape: &RandomShape) -> Result<(Self, Vec<F>), Error<F>> {
+        let z = shape.z(rng)?;
+        let a = shape.matrix(rng);
+        let b = shape.matrix(rng);
+        let az = CsrMatrix::from(&a).mul_vec(&z);
+        let bz = CsrMatrix::from(&b).mul_vec(&z);
+        let mut c = SparseMatrix {
+            n_rows: shape.m,
+            n_cols: shape.n,
+            vals: Vec::new(),
+        };
+        for r in 0..shape.m {
+            let row = shape.solved_row(rng, &z, az[r] * bz[r]);
+            c.vals.extend(row.into_iter().map(|(j, v)| (r, j, v)));
+        }
+        Ok((R1CS::new(shape.l, a, b, c)?, z))
+    }
+}
+
+impl<F: PrimeField> CCS<F> {
+    /// Random CCS with t matrices and t terms: t - 1 random terms of degree up to d,
+    /// the first of degree d, and a last degree 1 term whose matrix is solved row by
+    /// row so that the returned z = (1, x, w) satisfies it. Fails unless t >= 2 and d >= 1.
+    pub fn random<R: Rng>(
+        rng: &mut R,
+        shape: &RandomShape,
+        t: usize,
+        d: usize,
+    ) -> Result<(Self, Vec<F>), Error<F>> {
+        if t < 2 || d == 0 {
+            return Err(Error::InvalidRandomDegree(t, d));
+        }
+        let z = shape.z(rng)?;
+        let mut m_vec: Vec<SparseMatrix<F>> = (0..t - 1).map(|_| shape.matrix(rng)).collect();
+        let mut s_vec: Vec<Vec<usize>> = (0..t - 1)
+            .map(|i| {
+                let len = if i == 0 { d } else { rng.gen_range(1..=d) };
+                (0..len).map(|_| rng.gen_range(0..t - 1)).collect()
+            })
+            .collect();
+        let mut v: Vec<F> = (0..t - 1).map(|_| F::rand(rng)).collect();
+
+        let m_z: Vec<Vec<F>> = m_vec
+            .iter()
+            .map(|m_j| CsrMatrix::from(m_j).mul_vec(&z))
+            .collect();
+        let mut last = SparseMatrix {
+            n_rows: shape.m,
+            n_cols: shape.n,
+            vals: Vec::new(),
+        };
+        // sum of the random terms at every row
+        let mut sum = vec![F::zero(); shape.m];
+        for (s, c) in s_vec.iter().zip(&v) {
+            let term = s
+                .iter()
+                .fold(vec![*c; shape.m], |acc, j| hadamard(&acc, &m_z[*j]));
+            sum = vec_add_vec(&sum, &term);
+        }
+        for (r, sum_r) in sum.iter().enumerate() {
+            let row = shape.solved_row(rng, &z, -*sum_r);
+            last.vals.extend(row.into_iter().map(|(j, v)| (r, j, v)));
+        }
+        m_vec.push(last);
+        s_vec.push(vec![t - 1]);
+        v.push(F::one());
+        Ok((CCS::new(shape.l, m_vec, s_vec, v)?, z))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use ark_pallas::Fr;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use ark_std::{UniformRand, Zero};
+
+    use super::*;
+    use crate::transcript::poseidon::{tests::poseidon_test_config, PoseidonFieldTranscript};
+    use crate::transcript::FieldTranscript;
+    use crate::utils::mle::{eq_eval, vec_to_mle};
+    use crate::utils::sum_check::tests::{Goldilocks, GoldilocksExt2};
+    use crate::utils::sum_check::{SumCheck, VirtualPolynomial};
+
+    // shapes of various sizes, not powers of two and with sparse or dense rows
+    pub fn get_test_shapes() -> Vec<RandomShape> {
+        vec![
+            RandomShape {
+                m: 1,
+                n: 2,
+                l: 0,
+                density: 1.0,
+            },
+            RandomShape {
+                m: 7,
+                n: 13,
+                l: 3,
+                density: 0.3,
+            },
+            RandomShape {
+                m: 33,
+                n: 20,
+                l: 1,
+                density: 0.05,
+            },
+            RandomShape {
+                m: 64,
+                n: 128,
+                l: 10,
+                density: 0.5,
+            },
+        ]
+    }
+
+    // the fixed shapes followed by count shapes sampled from the seed
+    pub fn sample_test_shapes(seed: u64, count: usize) -> Vec<RandomShape> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let sampled = (0..count).map(|_| {
+            let n = rng.gen_range(2..48);
+            RandomShape {
+                m: rng.gen_range(1..48),
+                n,
+                l: rng.gen_range(0..n),
+                density: rng.gen_range(0.01..=1.0),
+            }
+        });
+        get_test_shapes().into_iter().chain(sampled).collect()
+    }
+
+    #[test]
+    fn test_random_shape_validate() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let shape = get_test_shapes()[1];
+        for bad in [
+            RandomShape { m: 0, ..shape },
+            RandomShape {
+                l: shape.n,
+                ..shape
+            },
+            RandomShape {
+                density: 0.0,
+                ..shape
+            },
+            RandomShape {
+                density: f64::NAN,
+                ..shape
+            },
+        ] {
+            assert!(matches!(
+                R1CS::<Fr>::random(&mut rng, &bad),
+                Err(Error::InvalidRandomShape(_))
+            ));
+        }
+        assert!(matches!(
+            CCS::<Fr>::random(&mut rng, &shape, 1, 2),
+            Err(Error::InvalidRandomDegree(1, 2))
+        ));
+        assert!(matches!(
+            CCS::<Fr>::random(&mut rng, &shape, 3, 0),
+            Err(Error::InvalidRandomDegree(3, 0))
+        ));
+
+        // rows have distinct columns, a dense row is full
+        let dense = RandomShape {
+            density: 1.0,
+            ..shape
+        };
+        let m = dense.matrix::<Fr, _>(&mut rng);
+        assert_eq!(m.vals.len(), dense.m * dense.n);
+    }
+
+    #[test]
+    fn test_random_r1cs() {
+        for (seed, shape) in sample_test_shapes(0, 16).iter().enumerate() {
+            let mut rng = StdRng::seed_from_u64(seed as u64);
+            let (r1cs, z) = R1CS::<Fr>::random(&mut rng, shape).unwrap();
+            assert_eq!(
+                (r1cs.a.n_rows, r1cs.a.n_cols, r1cs.l),
+                (shape.m, shape.n, shape.l)
+            );
+            r1cs.is_satisfied(&z).unwrap();
+            // the same seed gives the same shape
+            let mut rng = StdRng::seed_from_u64(seed as u64);
+            assert_eq!(
+                R1CS::<Fr>::random(&mut rng, shape).unwrap(),
+                (r1cs.clone(), z.clone())
+            );
+
+            let ccs = CCS::<Fr>::from_r1cs(r1cs).unwrap();
+            ccs.is_satisfied(&z).unwrap();
+            let padded = ccs.pad();
+            padded.is_satisfied(&padded.pad_z(&z)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_random_ccs() {
+        for (seed, shape) in sample_test_shapes(1, 16).iter().enumerate() {
+            let mut rng = StdRng::seed_from_u64(seed as u64);
+            let sampled = (rng.gen_range(2..8), rng.gen_range(1..6));
+            for (t, d) in [(2, 1), (3, 2), (5, 4), sampled] {
+                let (ccs, z) = CCS::<Fr>::random(&mut rng, shape, t, d).unwrap();
+                assert_eq!((ccs.m, ccs.n, ccs.t, ccs.d), (shape.m, shape.n, t, d));
+                ccs.is_satisfied(&z).unwrap();
+
+                let r1cs = ccs.to_r1cs();
+                r1cs.is_satisfied(&ccs.extend_z(&z)).unwrap();
+                let (optimized, map, _) = ccs.optimize();
+                optimized.is_satisfied(&map.apply(&z)).unwrap();
+
+                // a random z does not satisfy it
+                let wrong: Vec<Fr> = (0..shape.n).map(|_| Fr::rand(&mut rng)).collect();
+                assert!(ccs.is_satisfied(&wrong).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_random_ccs_small_field() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let shape = get_test_shapes()[1];
+        let (ccs, z) = CCS::<Goldilocks>::random(&mut rng, &shape, 3, 3).unwrap();
+        ccs.is_satisfied(&z).unwrap();
+        let ccs = ccs.pad();
+        let z = ccs.pad_z(&z);
+
+        // sum_x eq(beta, x) * sum_i c_i * prod_{j in S_i} (M_j * z)(x) = 0, beta is
+        // drawn from the extension as a base field beta gives a soundness error s / |F|
+        let config = poseidon_test_config::<Goldilocks>();
+        let mut ts_prove = PoseidonFieldTranscript::new(&config);
+        let mut ts_verify = PoseidonFieldTranscript::new(&config);
+        let beta: Vec<GoldilocksExt2> = (0..ccs.s).map(|_| ts_prove.challenge_ext()).collect();
+        let v_beta: Vec<GoldilocksExt2> = (0..ccs.s).map(|_| ts_verify.challenge_ext()).collect();
+        assert_eq!(beta, v_beta);
+        let mut poly = VirtualPolynomial::new(ccs.s);
+        let m_z: Vec<usize> = ccs
+            .m_vec
+            .iter()
+            .map(|m_j| poly.add_mle(vec_to_mle(ccs.s, &CsrMatrix::from(m_j).mul_vec(&z))))
+            .collect();
+        for (s, c) in ccs.s_vec.iter().zip(&ccs.v) {
+            poly.add_product(*c, s.iter().map(|j| m_z[*j]).collect());
+        }
+        assert!(poly.sum().is_zero());
+
+        let (proof, point) =
+            SumCheck::<Goldilocks, GoldilocksExt2>::prove_with_eq(&poly, &beta, &mut ts_prove);
+        let (expected, _) = SumCheck::<Goldilocks, GoldilocksExt2>::verify(
+            GoldilocksExt2::zero(),
+            &proof,
+            ccs.s,
+            poly.degree + 1,
+            &mut ts_verify,
+        )
+        .unwrap();
+        assert_eq!(expected, eq_eval(&beta, &point) * poly.evaluate(&point));
+    }
+}
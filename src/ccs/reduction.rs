@@ -0,0 +1,208 @@
+use ark_ff::PrimeField;
+
+use super::r1cs::{hadamard, CsrMatrix, SparseMatrix, R1CS};
+use super::{PreparedCCS, CCS};
+
+// a vector of m values, M_j * z or an auxiliary product
+#[derive(Clone, Copy, Debug)]
+enum Factor {
+    Matrix(usize),
+    Aux(usize),
+    One,
+}
+
+// how the terms of a CCS map to R1CS constraints
+struct Reduction {
+    // aux k = left o (M_right * z)
+    aux: Vec<(Factor, usize)>,
+    // terms left as c_i * factor
+    linear: Vec<(usize, Factor)>,
+    // one term kept as the A * B product of the final constraint
+    product: Option<(usize, Factor, usize)>,
+}
+
+impl<F: PrimeField> CCS<F> {
+    fn reduction(&self) -> Reduction {
+        let kept = self.s_vec.iter().position(|s| s.len() >= 2);
+        let mut reduction = Reduction {
+            aux: Vec::new(),
+            linear: Vec::new(),
+            product: None,
+        };
+        for (i, s) in self.s_vec.iter().enumerate() {
+            let Some((first, rest)) = s.split_first() else {
+                reduction.linear.push((i, Factor::One));
+                continue;
+            };
+            // the kept term stops one product early
+            let (rest, last) = match (kept == Some(i), rest.split_last()) {
+                (true, Some((last, rest))) => (rest, Some(*last)),
+                _ => (rest, None),
+            };
+            let mut left = Factor::Matrix(*first);
+            for j in rest {
+                reduction.aux.push((left, *j));
+                left = Factor::Aux(reduction.aux.len() - 1);
+            }
+            match last {
+                Some(last) => reduction.product = Some((i, left, last)),
+                None => reduction.linear.push((i, left)),
+            }
+        }
+        reduction
+    }
+
+    /// Degree 2 R1CS with the same (1, x, w) prefix: every product of more than two
+    /// matrices is split with m auxiliary columns per extra factor, appended after w.
+    /// One product keeps its last two factors in A * B with its coefficient moved to A,
+    /// the rest is moved to C, so a CCS from an R1CS gives back the same constraints.
+    /// Use extend_z to compute the new z.
+    pub fn to_r1cs(&self) -> R1CS<F> {
+        let reduction = self.reduction();
+        let m = self.m;
+        let n = self.n + m * reduction.aux.len();
+        let csr: Vec<CsrMatrix<F>> = self.m_vec.iter().map(CsrMatrix::from).collect();
+        // the row r of a factor as (column, value) entries
+        let row = |factor: Factor, r: usize| -> Vec<(usize, F)> {
+            match factor {
+                Factor::Matrix(j) => (csr[j].row_ptr[r]..csr[j].row_ptr[r + 1])
+                    .map(|k| (csr[j].col_idx[k], csr[j].vals[k]))
+                    .collect(),
+                Factor::Aux(k) => vec![(self.n + k * m + r, F::one())],
+                Factor::One => vec![(0, F::one())],
+            }
+        };
+
+        let num_rows = m * (1 + reduction.aux.len());
+        let mut a = SparseMatrix {
+            n_rows: num_rows,
+            n_cols: n,
+            vals: Vec::new(),
+        };
+        let mut b = a.clone();
+        let mut c = a.clone();
+        let sign = if reduction.product.is_some() {
+            -F::one()
+        } else {
+            F::one()
+        };
+        for r in 0..m {
+            let linear = reduction.linear.iter().flat_map(|(i, f)| {
+                row(*f, r)
+                    .into_iter()
+                    .map(move |(col, v)| (r, col, sign * self.v[*i] * v))
+            });
+            match reduction.product {
+                // c * L * R = -sum_i c_i * lin_i
+                Some((i, left, right)) => {
+                    a.vals.extend(
+                        row(left, r)
+                            .into_iter()
+                            .map(|(col, v)| (r, col, self.v[i] * v)),
+                    );
+                    b.vals.extend(
+                        row(Factor::Matrix(right), r)
+                            .into_iter()
+                            .map(|(col, v)| (r, col, v)),
+                    );
+                    c.vals.extend(linear);
+                }
+                // sum_i c_i * lin_i * 1 = 0
+                None => {
+                    a.vals.extend(linear);
+                    b.vals.push((r, 0, F::one()));
+                }
+            }
+        }
+        for (k, (left, right)) in reduction.aux.iter().enumerate() {
+            for r in 0..m {
+                let i = m * (k + 1) + r;
+                a.vals
+                    .extend(row(*left, r).into_iter().map(|(col, v)| (i, col, v)));
+                b.vals.extend(
+                    row(Factor::Matrix(*right), r)
+                        .into_iter()
+                        .map(|(col, v)| (i, col, v)),
+                );
+                c.vals.push((i, self.n + k * m + r, F::one()));
+            }
+        }
+        for matrix in [&mut a, &mut b, &mut c] {
+            matrix.vals.retain(|(_, _, v)| !v.is_zero());
+        }
+        // the first m rows and n columns keep their meaning
+        R1CS {
+            l: self.l,
+            a,
+            b,
+            c,
+            labels: self.labels.clone(),
+        }
+    }
+
+    /// maps z of the CCS to z of to_r1cs, appending the auxiliary products. Panics if
+    /// the CCS is not valid or z does not have n entries.
+    pub fn extend_z(&self, z: &[F]) -> Vec<F> {
+        self.prepare().unwrap().extend_z(z)
+    }
+}
+
+impl<F: PrimeField> PreparedCCS<'_, F> {
+    /// CCS::extend_z with the matrices already converted
+    pub fn extend_z(&self, z: &[F]) -> Vec<F> {
+        let reduction = self.ccs.reduction();
+        let m_z = self.mul_vec(z).unwrap();
+        let mut aux: Vec<Vec<F>> = Vec::with_capacity(reduction.aux.len());
+        for (left, right) in &reduction.aux {
+            let left = match left {
+                Factor::Matrix(j) => &m_z[*j],
+                Factor::Aux(k) => &aux[*k],
+                Factor::One => unreachable!(),
+            };
+            let product = hadamard(left, &m_z[*right]);
+            aux.push(product);
+        }
+        [vec![z.to_vec()], aux].concat().concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_pallas::Fr;
+
+    use super::*;
+    use crate::ccs::builder::tests::get_test_degree_5_ccs;
+    use crate::ccs::r1cs::tests::{get_test_r1cs, get_test_z};
+    use crate::ccs::r1cs::to_f_vec;
+
+    #[test]
+    fn test_r1cs_round_trip() {
+        let r1cs = get_test_r1cs::<Fr>();
+        let ccs = CCS::<Fr>::from_r1cs(r1cs.clone()).unwrap();
+        let back = ccs.to_r1cs();
+        assert_eq!(back.l, r1cs.l);
+        assert_eq!(
+            (back.a.n_rows, back.a.n_cols),
+            (r1cs.a.n_rows, r1cs.a.n_cols)
+        );
+        let z = get_test_z(3);
+        assert_eq!(ccs.extend_z(&z), z);
+        back.is_satisfied(&z).unwrap();
+    }
+
+    #[test]
+    fn test_degree_5_to_r1cs() {
+        let ccs = get_test_degree_5_ccs::<Fr>();
+        let r1cs = ccs.to_r1cs();
+        // x^5 needs 3 auxiliary products on each of the 2 rows
+        assert_eq!((r1cs.a.n_rows, r1cs.a.n_cols), (2 * 4, 5 + 2 * 3));
+
+        let z: Vec<Fr> = to_f_vec(vec![1, 35, 2, 3, 246]);
+        let z_r1cs = ccs.extend_z(&z);
+        assert_eq!(&z_r1cs[..5], &z[..]);
+        r1cs.is_satisfied(&z_r1cs).unwrap();
+
+        let wrong: Vec<Fr> = to_f_vec(vec![1, 35, 2, 3, 245]);
+        assert!(r1cs.is_satisfied(&ccs.extend_z(&wrong)).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use ark_ff::PrimeField;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::r1cs::{SparseMatrix, R1CS};
+use super::CCS;
+
+/// MSM sizes of the commitments computed by the NIFS prover for one fold
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct NIFSCost {
+    pub cm_w: usize,
+    pub cm_e: usize,
+    pub cm_t: usize,
+    // generators the Pedersen params need
+    pub generators: usize,
+}
+
+impl NIFSCost {
+    fn new<F: PrimeField>(r1cs: &R1CS<F>) -> Self {
+        let (cm_w, cm_e) = (r1cs.a.n_cols - r1cs.l - 1, r1cs.a.n_rows);
+        NIFSCost {
+            cm_w,
+            cm_e,
+            cm_t: cm_e,
+            generators: cm_w.max(cm_e),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Stats {
+    pub rows: usize,
+    pub columns: usize,
+    pub io: usize,
+    // non zero entries of each matrix, A, B and C for an R1CS
+    pub nnz: Vec<usize>,
+    // number of rows with k non zero entries over all the matrices, by k
+    pub row_density: BTreeMap<usize, usize>,
+    pub max_degree: usize,
+    pub nifs: NIFSCost,
+}
+
+impl Stats {
+    // rows and columns are given, a CCS may have no matrices
+    fn new<F: PrimeField>(
+        (rows, columns): (usize, usize),
+        matrices: &[&SparseMatrix<F>],
+        io: usize,
+        max_degree: usize,
+        nifs: NIFSCost,
+    ) -> Self {
+        let mut per_row = vec![0; rows];
+        for (r, _, _) in matrices.iter().flat_map(|m| &m.vals) {
+            per_row[*r] += 1;
+        }
+        let mut row_density = BTreeMap::new();
+        for k in per_row {
+            *row_density.entry(k).or_insert(0) += 1;
+        }
+        Stats {
+            rows,
+            columns,
+            io,
+            nnz: matrices.iter().map(|m| m.vals.len()).collect(),
+            row_density,
+            max_degree,
+            nifs,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let nnz: Vec<String> = self.nnz.iter().map(|n| n.to_string()).collect();
+        let density: Vec<String> = self
+            .row_density
+            .iter()
+            .map(|(k, rows)| format!("{}: {}", k, rows))
+            .collect();
+        writeln!(f, "{:<16}{}", "rows", self.rows)?;
+        writeln!(f, "{:<16}{}", "columns", self.columns)?;
+        writeln!(f, "{:<16}{}", "io", self.io)?;
+        writeln!(f, "{:<16}{}", "non-zeros", nnz.join(", "))?;
+        writeln!(f, "{:<16}{}", "row density", density.join(", "))?;
+        writeln!(f, "{:<16}{}", "max degree", self.max_degree)?;
+        writeln!(f, "{:<16}{}", "cm_w msm", self.nifs.cm_w)?;
+        writeln!(f, "{:<16}{}", "cm_e msm", self.nifs.cm_e)?;
+        writeln!(f, "{:<16}{}", "cm_t msm", self.nifs.cm_t)?;
+        write!(f, "{:<16}{}", "generators", self.nifs.generators)
+    }
+}
+
+impl<F: PrimeField> R1CS<F> {
+    pub fn stats(&self) -> Stats {
+        Stats::new(
+            (self.a.n_rows, self.a.n_cols),
+            &[&self.a, &self.b, &self.c],
+            self.l,
+            2,
+            NIFSCost::new(self),
+        )
+    }
+}
+
+impl<F: PrimeField> CCS<F> {
+    /// The NIFS cost is the one of folding to_r1cs, with its auxiliary columns
+    pub fn stats(&self) -> Stats {
+        let matrices: Vec<_> = self.m_vec.iter().collect();
+        Stats::new(
+            (self.m, self.n),
+            &matrices,
+            self.l,
+            self.d,
+            NIFSCost::new(&self.to_r1cs()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_pallas::Fr;
+
+    use super::*;
+    use crate::ccs::builder::tests::get_test_degree_5_ccs;
+    use crate::ccs::r1cs::tests::get_test_r1cs;
+
+    #[test]
+    fn test_r1cs_stats() {
+        let stats = get_test_r1cs::<Fr>().stats();
+        assert_eq!((stats.rows, stats.columns, stats.io), (4, 6, 1));
+        assert_eq!(stats.nnz, vec![6, 4, 4]);
+        // rows 0 and 1 have 3 entries, rows 2 and 3 have 4
+        assert_eq!(stats.row_density, BTreeMap::from([(3, 2), (4, 2)]));
+        assert_eq!(
+            stats.nifs,
+            NIFSCost {
+                cm_w: 4,
+                cm_e: 4,
+                cm_t: 4,
+                generators: 4
+            }
+        );
+        let table = stats.to_string();
+        assert!(table.contains("non-zeros       6, 4, 4"));
+        assert!(table.contains("row density     3: 2, 4: 2"));
+
+        let json: serde_json::Value = serde_json::from_str(&stats.to_json()).unwrap();
+        assert_eq!(json["nnz"], serde_json::json!([6, 4, 4]));
+        assert_eq!(json["nifs"]["cm_w"], 4);
+    }
+
+    #[test]
+    fn test_ccs_stats() {
+        let stats = get_test_degree_5_ccs::<Fr>().stats();
+        assert_eq!((stats.rows, stats.columns, stats.max_degree), (2, 5, 5));
+        assert_eq!(stats.nnz, vec![2, 2, 2]);
+        // to_r1cs has 8 rows and 3 auxiliary products on each row
+        assert_eq!((stats.nifs.cm_w, stats.nifs.cm_e), (3 + 6, 8));
+
+        // the public fields allow a CCS without matrices
+        let mut empty = get_test_degree_5_ccs::<Fr>();
+        (empty.m_vec, empty.s_vec, empty.v) = (Vec::new(), Vec::new(), Vec::new());
+        (empty.t, empty.q, empty.d) = (0, 0, 0);
+        let stats = empty.stats();
+        assert_eq!((stats.rows, stats.columns, stats.nnz.len()), (2, 5, 0));
+        assert_eq!(stats.row_density, BTreeMap::from([(0, 2)]));
+    }
+}
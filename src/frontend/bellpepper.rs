@@ -0,0 +1,195 @@
+use ark_ff::PrimeField;
+use bellpepper_core::{
+    Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable,
+};
+use std::marker::PhantomData;
+
+use super::Error;
+use crate::ccs::labels::Labels;
+use crate::ccs::r1cs::{SparseMatrix, R1CS};
+
+/// bellpepper ConstraintSystem over the ff field S that records the constraints and
+/// assignments over the arkworks field F, which must have the same modulus.
+/// Inputs come first in z = (1, x, w), so l is the number of alloc_input calls.
+pub struct BellpepperCS<S: ff::PrimeField, F: PrimeField> {
+    // inputs[0] is the constant one
+    inputs: Vec<F>,
+    aux: Vec<F>,
+    // one (A, B, C) per constraint
+    constraints: Vec<[Vec<(Index, F)>; 3]>,
+    _s: PhantomData<S>,
+}
+
+impl<S: ff::PrimeField, F: PrimeField> BellpepperCS<S, F> {
+    pub fn new() -> Result<Self, Error> {
+        // equal moduli iff -1 maps to -1
+        if to_ark::<S, F>(-S::ONE) != -F::one() {
+            return Err(Error::ModulusMismatch);
+        }
+        Ok(Self {
+            inputs: vec![F::one()],
+            aux: Vec::new(),
+            constraints: Vec::new(),
+            _s: PhantomData,
+        })
+    }
+
+    /// Synthesizes circuit with its witness and returns the R1CS and z = (1, x, w)
+    pub fn from_circuit(circuit: impl Circuit<S>) -> Result<(R1CS<F>, Vec<F>), Error> {
+        let mut cs = Self::new()?;
+        circuit.synthesize(&mut cs)?;
+        Ok(cs.to_r1cs())
+    }
+
+    pub fn to_r1cs(&self) -> (R1CS<F>, Vec<F>) {
+        let n_rows = self.constraints.len();
+        let n_cols = self.inputs.len() + self.aux.len();
+        let column = |index: &Index| match index {
+            Index::Input(i) => *i,
+            Index::Aux(i) => self.inputs.len() + i,
+        };
+        let mut matrices: Vec<SparseMatrix<F>> = (0..3)
+            .map(|_| SparseMatrix {
+                n_rows,
+                n_cols,
+                vals: Vec::new(),
+            })
+            .collect();
+        for (i, constraint) in self.constraints.iter().enumerate() {
+            for (m, lc) in matrices.iter_mut().zip(constraint) {
+                m.vals
+                    .extend(lc.iter().map(|(index, v)| (i, column(index), *v)));
+            }
+        }
+
+        let c = matrices.pop().unwrap();
+        let b = matrices.pop().unwrap();
+        let a = matrices.pop().unwrap();
+        let r1cs = R1CS {
+            l: self.inputs.len() - 1,
+            a,
+            b,
+            c,
+            labels: Labels::default(),
+        };
+        (r1cs, [self.inputs.clone(), self.aux.clone()].concat())
+    }
+}
+
+// ff fields used with bellpepper (pasta, bls12-381, bn256) have little endian reprs
+fn to_ark<S: ff::PrimeField, F: PrimeField>(s: S) -> F {
+    F::from_le_bytes_mod_order(s.to_repr().as_ref())
+}
+
+fn convert_lc<S: ff::PrimeField, F: PrimeField>(lc: LinearCombination<S>) -> Vec<(Index, F)> {
+    lc.iter()
+        .filter(|(_, v)| !bool::from(v.is_zero()))
+        .map(|(var, v)| (var.get_unchecked(), to_ark(*v)))
+        .collect()
+}
+
+impl<S: ff::PrimeField, F: PrimeField> ConstraintSystem<S> for BellpepperCS<S, F> {
+    type Root = Self;
+
+    fn alloc<V, A, AR>(&mut self, _annotation: A, f: V) -> Result<Variable, SynthesisError>
+    where
+        V: FnOnce() -> Result<S, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.aux.push(to_ark(f()?));
+        Ok(Variable::new_unchecked(Index::Aux(self.aux.len() - 1)))
+    }
+
+    fn alloc_input<V, A, AR>(&mut self, _annotation: A, f: V) -> Result<Variable, SynthesisError>
+    where
+        V: FnOnce() -> Result<S, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.inputs.push(to_ark(f()?));
+        Ok(Variable::new_unchecked(Index::Input(self.inputs.len() - 1)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _annotation: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<S>) -> LinearCombination<S>,
+        LB: FnOnce(LinearCombination<S>) -> LinearCombination<S>,
+        LC: FnOnce(LinearCombination<S>) -> LinearCombination<S>,
+    {
+        self.constraints.push([
+            convert_lc(a(LinearCombination::zero())),
+            convert_lc(b(LinearCombination::zero())),
+            convert_lc(c(LinearCombination::zero())),
+        ]);
+    }
+
+    fn push_namespace<NR, N>(&mut self, _name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self) {}
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_pallas::Fr;
+    use bellpepper_core::num::AllocatedNum;
+    use pasta_curves::Fq;
+
+    use super::*;
+    use crate::ccs::CCS;
+
+    // x^3 + x + 5 = y with x and y public
+    struct CubicCircuit {
+        x: Fq,
+    }
+
+    impl Circuit<Fq> for CubicCircuit {
+        fn synthesize<CS: ConstraintSystem<Fq>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+            let x_val = self.x;
+            let x = AllocatedNum::alloc_input(cs.namespace(|| "x"), || Ok(x_val))?;
+            let x2 = x.square(cs.namespace(|| "x2"))?;
+            let x3 = x2.mul(cs.namespace(|| "x3"), &x)?;
+            let y = AllocatedNum::alloc_input(cs.namespace(|| "y"), || {
+                Ok(x_val * x_val * x_val + x_val + Fq::from(5))
+            })?;
+            cs.enforce(
+                || "x3 + x + 5 = y",
+                |lc| lc + x3.get_variable() + x.get_variable() + (Fq::from(5), CS::one()),
+                |lc| lc + CS::one(),
+                |lc| lc + y.get_variable(),
+            );
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_bellpepper_cs() {
+        let x = Fq::from(3);
+        let (r1cs, z) = BellpepperCS::<Fq, Fr>::from_circuit(CubicCircuit { x }).unwrap();
+        assert_eq!(r1cs.l, 2);
+        assert_eq!(r1cs.a.n_rows, 3);
+        let (_, io) = r1cs.split_z(&z);
+        assert_eq!(io, vec![Fr::from(3u64), Fr::from(35u64)]);
+        CCS::<Fr>::from_r1cs(r1cs)
+            .unwrap()
+            .is_satisfied(&z)
+            .unwrap();
+
+        // pasta Fp is the pallas base field, not its scalar field
+        assert!(matches!(
+            BellpepperCS::<pasta_curves::Fp, Fr>::new(),
+            Err(Error::ModulusMismatch)
+        ));
+    }
+}
@@ -0,0 +1,314 @@
+use ark_ff::{BigInteger, PrimeField};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use super::Error;
+use crate::ccs::labels::Labels;
+use crate::ccs::r1cs::{SparseMatrix, R1CS};
+
+// section ids of the iden3 binary formats
+const R1CS_HEADER: u32 = 1;
+const R1CS_CONSTRAINTS: u32 = 2;
+const R1CS_WIRE_TO_LABEL: u32 = 3;
+const WTNS_HEADER: u32 = 1;
+const WTNS_VALUES: u32 = 2;
+
+/// Reads a circom .r1cs file. Circom numbers the wires as
+/// (1, public outputs, public inputs, private signals), so the wires are z = (1, x, w)
+/// with l = outputs + public inputs, and constraints are A * B - C = 0.
+pub fn read_r1cs<F: PrimeField, R: Read>(reader: R) -> Result<R1CS<F>, Error> {
+    let sections = read_sections(reader, b"r1cs", &[1])?;
+
+    let mut header = section(&sections, R1CS_HEADER)?;
+    read_prime::<F>(&mut header)?;
+    let n_wires = read_u32(&mut header)? as usize;
+    let n_pub_out = read_u32(&mut header)? as usize;
+    let n_pub_in = read_u32(&mut header)? as usize;
+    let _n_prv_in = read_u32(&mut header)?;
+    let _n_labels = read_u64(&mut header)?;
+    let n_constraints = read_u32(&mut header)? as usize;
+
+    let mut matrices: Vec<SparseMatrix<F>> = (0..3)
+        .map(|_| SparseMatrix {
+            n_rows: n_constraints,
+            n_cols: n_wires,
+            vals: Vec::new(),
+        })
+        .collect();
+    let mut constraints = section(&sections, R1CS_CONSTRAINTS)?;
+    for i in 0..n_constraints {
+        for m in matrices.iter_mut() {
+            let n_terms = read_u32(&mut constraints)?;
+            for _ in 0..n_terms {
+                let wire = read_u32(&mut constraints)? as usize;
+                if wire >= n_wires {
+                    return Err(Error::InvalidWire(wire, n_wires));
+                }
+                let coeff = F::deserialize_uncompressed(&mut constraints)?;
+                m.vals.push((i, wire, coeff));
+            }
+        }
+    }
+
+    let c = matrices.pop().unwrap();
+    let b = matrices.pop().unwrap();
+    let a = matrices.pop().unwrap();
+    Ok(R1CS {
+        l: n_pub_out + n_pub_in,
+        a,
+        b,
+        c,
+        labels: Labels::default(),
+    })
+}
+
+/// Reads a circom .wtns file, the values are the wires of the .r1cs file, i.e. z = (1, x, w)
+pub fn read_wtns<F: PrimeField, R: Read>(reader: R) -> Result<Vec<F>, Error> {
+    let sections = read_sections(reader, b"wtns", &[1, 2])?;
+
+    let mut header = section(&sections, WTNS_HEADER)?;
+    read_prime::<F>(&mut header)?;
+    let n_values = read_u32(&mut header)?;
+
+    let mut values = section(&sections, WTNS_VALUES)?;
+    (0..n_values)
+        .map(|_| Ok(F::deserialize_uncompressed(&mut values)?))
+        .collect()
+}
+
+/// Writes r1cs as a circom .r1cs file. All io is declared as public inputs and the
+/// labels are the wire ids, since R1CS does not keep that information.
+pub fn write_r1cs<F: PrimeField, W: Write>(r1cs: &R1CS<F>, mut writer: W) -> Result<(), Error> {
+    let n_wires = r1cs.a.n_cols;
+    let n_constraints = r1cs.a.n_rows;
+
+    let mut header = prime_bytes::<F>();
+    header.extend(to_u32(n_wires)?.to_le_bytes());
+    header.extend(0u32.to_le_bytes());
+    header.extend(to_u32(r1cs.l)?.to_le_bytes());
+    header.extend(0u32.to_le_bytes());
+    header.extend((n_wires as u64).to_le_bytes());
+    header.extend(to_u32(n_constraints)?.to_le_bytes());
+
+    // group the entries of every matrix by row
+    let rows: Vec<Vec<Vec<(usize, F)>>> = [&r1cs.a, &r1cs.b, &r1cs.c]
+        .iter()
+        .map(|m| {
+            let mut rows = vec![Vec::new(); n_constraints];
+            for (i, j, v) in &m.vals {
+                rows[*i].push((*j, *v));
+            }
+            rows
+        })
+        .collect();
+    let mut constraints = Vec::new();
+    for i in 0..n_constraints {
+        for m in &rows {
+            constraints.extend(to_u32(m[i].len())?.to_le_bytes());
+            for (j, v) in &m[i] {
+                constraints.extend(to_u32(*j)?.to_le_bytes());
+                v.serialize_uncompressed(&mut constraints)?;
+            }
+        }
+    }
+
+    let labels: Vec<u8> = (0..n_wires as u64).flat_map(|i| i.to_le_bytes()).collect();
+
+    write_sections(
+        &mut writer,
+        b"r1cs",
+        1,
+        &[
+            (R1CS_HEADER, header),
+            (R1CS_CONSTRAINTS, constraints),
+            (R1CS_WIRE_TO_LABEL, labels),
+        ],
+    )
+}
+
+/// Writes z = (1, x, w) as a circom .wtns file
+pub fn write_wtns<F: PrimeField, W: Write>(z: &[F], mut writer: W) -> Result<(), Error> {
+    let mut header = prime_bytes::<F>();
+    header.extend(to_u32(z.len())?.to_le_bytes());
+    let mut values = Vec::new();
+    for v in z {
+        v.serialize_uncompressed(&mut values)?;
+    }
+    write_sections(
+        &mut writer,
+        b"wtns",
+        2,
+        &[(WTNS_HEADER, header), (WTNS_VALUES, values)],
+    )
+}
+
+// counts and wire ids are u32 in both formats
+fn to_u32(n: usize) -> Result<u32, Error> {
+    u32::try_from(n).map_err(|_| Error::TooLarge(n))
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32, Error> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(reader: &mut impl Read) -> Result<u64, Error> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+// sections may come in any order, so they are all loaded before parsing
+fn read_sections<R: Read>(
+    mut reader: R,
+    magic: &[u8; 4],
+    versions: &[u32],
+) -> Result<HashMap<u32, Vec<u8>>, Error> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    if &buf != magic {
+        return Err(Error::InvalidMagic(String::from_utf8_lossy(magic).into()));
+    }
+    let version = read_u32(&mut reader)?;
+    if !versions.contains(&version) {
+        return Err(Error::UnsupportedVersion(version));
+    }
+    let n_sections = read_u32(&mut reader)?;
+    let mut sections = HashMap::new();
+    for _ in 0..n_sections {
+        let id = read_u32(&mut reader)?;
+        let size = read_u64(&mut reader)?;
+        let mut content = Vec::new();
+        (&mut reader).take(size).read_to_end(&mut content)?;
+        if content.len() as u64 != size {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+        sections.insert(id, content);
+    }
+    Ok(sections)
+}
+
+fn section(sections: &HashMap<u32, Vec<u8>>, id: u32) -> Result<&[u8], Error> {
+    sections
+        .get(&id)
+        .map(|s| s.as_slice())
+        .ok_or(Error::MissingSection(id))
+}
+
+fn write_sections<W: Write>(
+    writer: &mut W,
+    magic: &[u8; 4],
+    version: u32,
+    sections: &[(u32, Vec<u8>)],
+) -> Result<(), Error> {
+    writer.write_all(magic)?;
+    writer.write_all(&version.to_le_bytes())?;
+    writer.write_all(&to_u32(sections.len())?.to_le_bytes())?;
+    for (id, content) in sections {
+        writer.write_all(&id.to_le_bytes())?;
+        writer.write_all(&(content.len() as u64).to_le_bytes())?;
+        writer.write_all(content)?;
+    }
+    Ok(())
+}
+
+// field element size followed by the prime, little endian
+fn prime_bytes<F: PrimeField>() -> Vec<u8> {
+    let n8 = F::zero().uncompressed_size();
+    let mut modulus = F::MODULUS.to_bytes_le();
+    modulus.resize(n8, 0);
+    [(n8 as u32).to_le_bytes().to_vec(), modulus].concat()
+}
+
+fn read_prime<F: PrimeField>(reader: &mut impl Read) -> Result<(), Error> {
+    let n8 = read_u32(reader)? as usize;
+    let expected = prime_bytes::<F>();
+    if n8 != expected.len() - 4 {
+        return Err(Error::InvalidFieldSize(n8, expected.len() - 4));
+    }
+    let mut prime = vec![0u8; n8];
+    reader.read_exact(&mut prime)?;
+    if prime != expected[4..] {
+        return Err(Error::ModulusMismatch);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bn254::Fr;
+
+    use super::*;
+    use crate::ccs::r1cs::tests::{get_test_r1cs, get_test_z};
+    use crate::ccs::CCS;
+
+    #[test]
+    fn test_circom_round_trip() {
+        let r1cs = get_test_r1cs::<Fr>();
+        let z = get_test_z::<Fr>(3);
+
+        let mut r1cs_bytes = Vec::new();
+        write_r1cs(&r1cs, &mut r1cs_bytes).unwrap();
+        let mut wtns_bytes = Vec::new();
+        write_wtns(&z, &mut wtns_bytes).unwrap();
+
+        let r1cs_read = read_r1cs::<Fr, _>(r1cs_bytes.as_slice()).unwrap();
+        let z_read = read_wtns::<Fr, _>(wtns_bytes.as_slice()).unwrap();
+        assert_eq!(r1cs_read, r1cs);
+        assert_eq!(z_read, z);
+        CCS::<Fr>::from_r1cs(r1cs_read)
+            .unwrap()
+            .is_satisfied(&z_read)
+            .unwrap();
+
+        // the files are over bn254, reading them over pallas must fail
+        assert!(matches!(
+            read_r1cs::<ark_pallas::Fr, _>(r1cs_bytes.as_slice()),
+            Err(Error::ModulusMismatch)
+        ));
+        assert!(matches!(
+            read_wtns::<ark_pallas::Fr, _>(wtns_bytes.as_slice()),
+            Err(Error::ModulusMismatch)
+        ));
+        assert!(matches!(
+            read_wtns::<Fr, _>(r1cs_bytes.as_slice()),
+            Err(Error::InvalidMagic(_))
+        ));
+    }
+
+    // Cubic circuit in circom's own layout: wires (1, y, x, x2, x3) with y = x^3 + x + 5,
+    // signs as circom writes them (-a * b - -c = 0), and 6 labels for 5 wires since
+    // `signal s <== x3` is merged into x3 by the -O1 simplification.
+    //   x2 <== x * x;  x3 <== x2 * x;  s <== x3;  y <== s + x + 5;
+    #[test]
+    fn test_circom_fixture() {
+        let r1cs = read_r1cs::<Fr, _>(&include_bytes!("fixtures/cubic.r1cs")[..]).unwrap();
+        let z = read_wtns::<Fr, _>(&include_bytes!("fixtures/cubic.wtns")[..]).unwrap();
+        assert_eq!((r1cs.l, r1cs.a.n_rows, r1cs.a.n_cols), (2, 3, 5));
+        assert_eq!(
+            z,
+            [1, 35, 3, 9, 27].map(Fr::from).to_vec(),
+            "z = (1, y, x, x2, x3)"
+        );
+        let ccs = CCS::<Fr>::from_r1cs(r1cs).unwrap();
+        ccs.is_satisfied(&z).unwrap();
+
+        let mut bad_z = z.clone();
+        bad_z[1] = Fr::from(36);
+        assert!(ccs.is_satisfied(&bad_z).is_err());
+    }
+
+    #[test]
+    fn test_circom_too_large() {
+        let mut r1cs = get_test_r1cs::<Fr>();
+        for m in [&mut r1cs.a, &mut r1cs.b, &mut r1cs.c] {
+            m.n_cols = u32::MAX as usize + 1;
+        }
+        assert!(matches!(
+            write_r1cs(&r1cs, &mut Vec::new()),
+            Err(Error::TooLarge(n)) if n == u32::MAX as usize + 1
+        ));
+        assert!(matches!(to_u32(u32::MAX as usize), Ok(u32::MAX)));
+    }
+}
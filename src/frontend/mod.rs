@@ -0,0 +1,38 @@
+use ark_serialize::SerializationError;
+use thiserror::Error;
+
+pub mod bellpepper;
+pub mod circom;
+pub mod noir;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Serialization(#[from] SerializationError),
+    #[error("Invalid magic, expected {0:?}")]
+    InvalidMagic(String),
+    #[error("Unsupported version {0}")]
+    UnsupportedVersion(u32),
+    #[error("Missing section {0}")]
+    MissingSection(u32),
+    #[error("Field elements are {0} bytes, expected {1}")]
+    InvalidFieldSize(usize, usize),
+    #[error("File prime does not match the field modulus")]
+    ModulusMismatch,
+    #[error("Wire {0} out of range, circuit has {1} wires")]
+    InvalidWire(usize, usize),
+    #[error("{0} does not fit in the u32 fields of the file format")]
+    TooLarge(usize),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("Unsupported opcode {0}")]
+    UnsupportedOpcode(String),
+    #[error("Missing value for witness {0}")]
+    MissingWitness(u32),
+    #[error("Invalid field element {0}")]
+    InvalidFieldElement(String),
+    #[error(transparent)]
+    BellpepperSynthesis(#[from] bellpepper_core::SynthesisError),
+}
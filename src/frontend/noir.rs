@@ -0,0 +1,303 @@
+use ark_ff::{BigInteger, PrimeField};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use super::Error;
+use crate::ccs::labels::Labels;
+use crate::ccs::r1cs::{SparseMatrix, R1CS};
+
+/// ACIR witness index
+pub type Witness = u32;
+
+/// Witness values by index, field elements are hex strings as in the ACIR JSON
+pub type WitnessMap = BTreeMap<Witness, String>;
+
+/// sum q_i * a_i * b_i + sum c_j * w_j + q_c = 0
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Expression {
+    pub mul_terms: Vec<(String, Witness, Witness)>,
+    pub linear_combinations: Vec<(String, Witness)>,
+    pub q_c: String,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub enum Opcode {
+    #[serde(alias = "Arithmetic")]
+    AssertZero(Expression),
+    BlackBoxFuncCall(serde_json::Value),
+    Directive(serde_json::Value),
+    Brillig(serde_json::Value),
+    MemoryOp(serde_json::Value),
+    MemoryInit(serde_json::Value),
+    Call(serde_json::Value),
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Circuit {
+    pub current_witness_index: u32,
+    pub opcodes: Vec<Opcode>,
+    #[serde(default)]
+    pub private_parameters: BTreeSet<Witness>,
+    #[serde(default)]
+    pub public_parameters: BTreeSet<Witness>,
+    #[serde(default)]
+    pub return_values: BTreeSet<Witness>,
+}
+
+impl Circuit {
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Converts the circuit and its solved witness map into an R1CS and z = (1, x, w).
+    /// x are the public parameters followed by the return values, w the other witnesses
+    /// in index order, followed by one auxiliary variable for every mul term but the
+    /// last of each expression. Brillig and Directive opcodes only compute witnesses, so
+    /// they add no constraints and their outputs are read from the witness map.
+    pub fn to_r1cs<F: PrimeField>(
+        &self,
+        witness_map: &WitnessMap,
+    ) -> Result<(R1CS<F>, Vec<F>), Error> {
+        let mut expressions = Vec::with_capacity(self.opcodes.len());
+        for opcode in &self.opcodes {
+            match opcode {
+                Opcode::AssertZero(e) => expressions.push(e),
+                Opcode::BlackBoxFuncCall(call) => {
+                    let name = match call {
+                        serde_json::Value::Object(o) => o.keys().next().cloned(),
+                        _ => None,
+                    };
+                    return Err(Error::UnsupportedOpcode(format!(
+                        "BlackBoxFuncCall({})",
+                        name.unwrap_or_default()
+                    )));
+                }
+                // unconstrained hints, their outputs are already in the witness map
+                Opcode::Directive(_) | Opcode::Brillig(_) => {}
+                Opcode::MemoryOp(_) => return Err(Error::UnsupportedOpcode("MemoryOp".into())),
+                Opcode::MemoryInit(_) => return Err(Error::UnsupportedOpcode("MemoryInit".into())),
+                Opcode::Call(_) => return Err(Error::UnsupportedOpcode("Call".into())),
+            }
+        }
+
+        // every witness that shows up, public ones first
+        let public: Vec<Witness> = self
+            .public_parameters
+            .iter()
+            .chain(self.return_values.difference(&self.public_parameters))
+            .copied()
+            .collect();
+        let mut private = BTreeSet::new();
+        private.extend(self.private_parameters.iter());
+        for e in &expressions {
+            private.extend(e.mul_terms.iter().flat_map(|(_, a, b)| [*a, *b]));
+            private.extend(e.linear_combinations.iter().map(|(_, w)| *w));
+        }
+        let mut columns = HashMap::new();
+        let mut z = vec![F::one()];
+        for w in public
+            .iter()
+            .chain(private.difference(&public.iter().copied().collect()))
+        {
+            let value = witness_map.get(w).ok_or(Error::MissingWitness(*w))?;
+            columns.insert(*w, z.len());
+            z.push(parse_field(value)?);
+        }
+
+        // rows are (A, B, C) as (column, value) lists
+        let mut rows = Vec::new();
+        for e in &expressions {
+            let mut c: Vec<(usize, F)> = e
+                .linear_combinations
+                .iter()
+                .map(|(q, w)| Ok((columns[w], -parse_field::<F>(q)?)))
+                .collect::<Result<_, Error>>()?;
+            c.push((0, -parse_field::<F>(&e.q_c)?));
+
+            let Some(((q, a, b), rest)) = e.mul_terms.split_last() else {
+                // linear expression: (lc + q_c) * 1 = 0
+                let a = c.into_iter().map(|(j, v)| (j, -v)).collect();
+                rows.push((a, vec![(0, F::one())], Vec::new()));
+                continue;
+            };
+            for (q_i, a_i, b_i) in rest {
+                let t = z.len();
+                z.push(z[columns[a_i]] * z[columns[b_i]]);
+                rows.push((
+                    vec![(columns[a_i], F::one())],
+                    vec![(columns[b_i], F::one())],
+                    vec![(t, F::one())],
+                ));
+                c.push((t, -parse_field::<F>(q_i)?));
+            }
+            rows.push((
+                vec![(columns[a], parse_field(q)?)],
+                vec![(columns[b], F::one())],
+                c,
+            ));
+        }
+
+        let n_cols = z.len();
+        let mut matrices: Vec<SparseMatrix<F>> = (0..3)
+            .map(|_| SparseMatrix {
+                n_rows: rows.len(),
+                n_cols,
+                vals: Vec::new(),
+            })
+            .collect();
+        for (i, (a, b, c)) in rows.into_iter().enumerate() {
+            for (m, lc) in matrices.iter_mut().zip([a, b, c]) {
+                m.vals.extend(
+                    lc.into_iter()
+                        .filter(|(_, v)| !v.is_zero())
+                        .map(|(j, v)| (i, j, v)),
+                );
+            }
+        }
+        let c = matrices.pop().unwrap();
+        let b = matrices.pop().unwrap();
+        let a = matrices.pop().unwrap();
+        Ok((
+            R1CS {
+                l: public.len(),
+                a,
+                b,
+                c,
+                labels: Labels::default(),
+            },
+            z,
+        ))
+    }
+}
+
+/// Parses a witness map given as a JSON object from witness index to hex field element
+pub fn witness_map_from_json(json: &str) -> Result<WitnessMap, Error> {
+    Ok(serde_json::from_str(json)?)
+}
+
+// hex, with or without 0x, must be smaller than the modulus
+fn parse_field<F: PrimeField>(s: &str) -> Result<F, Error> {
+    let invalid = || Error::InvalidFieldElement(s.to_string());
+    let hex = s.strip_prefix("0x").unwrap_or(s);
+    let digits = hex
+        .chars()
+        .map(|c| c.to_digit(16).map(|d| d as u8))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(invalid)?;
+    // big endian bytes, two digits each
+    let mut bytes = vec![0u8; digits.len().div_ceil(2)];
+    for (i, d) in digits.iter().rev().enumerate() {
+        let k = bytes.len() - 1 - i / 2;
+        bytes[k] |= d << (4 * (i % 2));
+    }
+    let value = F::from_be_bytes_mod_order(&bytes);
+    let trim = |b: &[u8]| {
+        b.iter()
+            .skip_while(|x| **x == 0)
+            .copied()
+            .collect::<Vec<u8>>()
+    };
+    if trim(&value.into_bigint().to_bytes_be()) != trim(&bytes) {
+        return Err(invalid());
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bn254::Fr;
+    use ark_ff::Field;
+
+    use super::*;
+    use crate::ccs::CCS;
+
+    const MINUS_ONE: &str = "0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000000";
+
+    // x^3 + x + 5 = y and u = x^2 + 2 * x^3, x public, y returned
+    fn test_circuit_json() -> String {
+        format!(
+            r#"{{
+                "current_witness_index": 3,
+                "opcodes": [
+                    {{"AssertZero": {{"mul_terms": [["0x01", 0, 0]], "linear_combinations": [["{m}", 2]], "q_c": "0x00"}}}},
+                    {{"AssertZero": {{"mul_terms": [["0x01", 2, 0]], "linear_combinations": [["0x01", 0], ["{m}", 1]], "q_c": "0x05"}}}},
+                    {{"AssertZero": {{"mul_terms": [["0x01", 0, 0], ["0x02", 0, 2]], "linear_combinations": [["{m}", 3]], "q_c": "0x00"}}}}
+                ],
+                "private_parameters": [],
+                "public_parameters": [0],
+                "return_values": [1]
+            }}"#,
+            m = MINUS_ONE
+        )
+    }
+
+    #[test]
+    fn test_acir_to_r1cs() {
+        let circuit = Circuit::from_json(&test_circuit_json()).unwrap();
+        let witness =
+            witness_map_from_json(r#"{"0": "0x03", "1": "0x23", "2": "0x09", "3": "0x3f"}"#)
+                .unwrap();
+        let (r1cs, z) = circuit.to_r1cs::<Fr>(&witness).unwrap();
+
+        assert_eq!(r1cs.l, 2);
+        // 3 opcodes plus one auxiliary row for the extra mul term
+        assert_eq!(r1cs.a.n_rows, 4);
+        let (_, x) = r1cs.split_z(&z);
+        assert_eq!(x, vec![Fr::from(3u64), Fr::from(35u64)]);
+        let ccs = CCS::<Fr>::from_r1cs(r1cs).unwrap();
+        ccs.is_satisfied(&z).unwrap();
+
+        let wrong =
+            witness_map_from_json(r#"{"0": "0x03", "1": "0x24", "2": "0x09", "3": "0x3f"}"#)
+                .unwrap();
+        let (_, z) = circuit.to_r1cs::<Fr>(&wrong).unwrap();
+        assert!(ccs.is_satisfied(&z).is_err());
+
+        let missing = witness_map_from_json(r#"{"0": "0x03", "1": "0x23", "2": "0x09"}"#).unwrap();
+        assert!(matches!(
+            circuit.to_r1cs::<Fr>(&missing),
+            Err(Error::MissingWitness(3))
+        ));
+    }
+
+    #[test]
+    fn test_acir_rejects_black_box() {
+        let json = r#"{
+            "current_witness_index": 0,
+            "opcodes": [{"BlackBoxFuncCall": {"RANGE": {"input": {"witness": 0, "num_bits": 8}}}}],
+            "public_parameters": [0]
+        }"#;
+        let circuit = Circuit::from_json(json).unwrap();
+        let witness = witness_map_from_json(r#"{"0": "0x03"}"#).unwrap();
+        match circuit.to_r1cs::<Fr>(&witness) {
+            Err(Error::UnsupportedOpcode(name)) => assert_eq!(name, "BlackBoxFuncCall(RANGE)"),
+            _ => panic!("black box opcode must be rejected"),
+        }
+    }
+
+    // x != 0 through a Brillig inverse hint, and x = b0 + 2 * b1 with the bits from a
+    // ToLeRadix directive
+    #[test]
+    fn test_acir_skips_hints() {
+        let circuit = Circuit::from_json(include_str!("fixtures/bits.json")).unwrap();
+        let inv = "0x2042def740cbc01bd03583cf0100e59370229adafbd0f5b62d414e62a0000001";
+        let witness = witness_map_from_json(&format!(
+            r#"{{"0": "0x03", "1": "{inv}", "2": "0x01", "3": "0x01"}}"#
+        ))
+        .unwrap();
+        let (r1cs, z) = circuit.to_r1cs::<Fr>(&witness).unwrap();
+
+        // only the 4 AssertZero opcodes become rows
+        assert_eq!((r1cs.l, r1cs.a.n_rows), (1, 4));
+        assert_eq!(z[2], Fr::from(3u64).inverse().unwrap());
+        let ccs = CCS::<Fr>::from_r1cs(r1cs).unwrap();
+        ccs.is_satisfied(&z).unwrap();
+
+        let wrong = witness_map_from_json(&format!(
+            r#"{{"0": "0x03", "1": "{inv}", "2": "0x00", "3": "0x01"}}"#
+        ))
+        .unwrap();
+        let (_, z) = circuit.to_r1cs::<Fr>(&wrong).unwrap();
+        assert!(ccs.is_satisfied(&z).is_err());
+    }
+}
@@ -150,7 +150,8 @@ mod tests {
         let ci2 = w2.commit(&params, x2);
 
         let r = Fr::rand(&mut rng);
-        let (_w3, ci3, _t, cm_t) = NIFS::prove(&params, r, &r1cs, &w1, &ci1, &w2, &ci2);
+        let (_w3, ci3, _t, cm_t) =
+            NIFS::prove(&params, r, &r1cs.prepare().unwrap(), &w1, &ci1, &w2, &ci2);
 
         let cs = ConstraintSystem::<Fr>::new_ref();
         let r_var = FpVar::<Fr>::new_witness(cs.clone(), || Ok(r)).unwrap();
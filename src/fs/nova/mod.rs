@@ -1,11 +1,12 @@
 use crate::pedersen::{Params as PedersenParams, Pedersen};
 use ark_ec::CurveGroup;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::{One, Zero};
 
 pub mod circuits;
 pub mod nifs;
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
 pub struct CommittedInstance<C: CurveGroup> {
     pub cm_e: C,
     pub u: C::ScalarField,
@@ -24,7 +25,7 @@ impl<C: CurveGroup> CommittedInstance<C> {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
 pub struct Witness<C: CurveGroup> {
     // e is error matrix = F^m, m is r1cs row number
     pub e: Vec<C::ScalarField>,
@@ -43,6 +44,16 @@ impl<C: CurveGroup> Witness<C> {
         }
     }
 
+    /// Extends w and e with zeros to the sizes of a padded R1CS, the commitments
+    /// do not change since the new entries are zero. Panics if a size is smaller.
+    pub fn pad(&self, w_len: usize, e_len: usize) -> Self {
+        assert!(w_len >= self.w.len() && e_len >= self.e.len());
+        let mut padded = self.clone();
+        padded.w.resize(w_len, C::ScalarField::zero());
+        padded.e.resize(e_len, C::ScalarField::zero());
+        padded
+    }
+
     pub fn commit(
         &self,
         params: &PedersenParams<C>,
@@ -53,3 +64,48 @@ impl<C: CurveGroup> Witness<C> {
         CommittedInstance { cm_e, u: C::ScalarField::one(), cm_w, x }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ark_pallas::{Fr, Projective};
+    use ark_serialize::{Compress, Validate};
+    use ark_std::UniformRand;
+
+    use super::*;
+
+    #[test]
+    fn test_instance_serialization() {
+        let mut rng = ark_std::test_rng();
+        let params = Pedersen::<Projective>::new_params(&mut rng, 8);
+        let w: Vec<Fr> = (0..5).map(|_| Fr::rand(&mut rng)).collect();
+        let witness = Witness::<Projective>::new(w, 8);
+        let ci = witness.commit(&params, vec![Fr::rand(&mut rng)]);
+        for compress in [Compress::Yes, Compress::No] {
+            let mut bytes = Vec::new();
+            witness.serialize_with_mode(&mut bytes, compress).unwrap();
+            let read = Witness::deserialize_with_mode(&bytes[..], compress, Validate::Yes);
+            assert_eq!(read.unwrap(), witness);
+            let mut bytes = Vec::new();
+            ci.serialize_with_mode(&mut bytes, compress).unwrap();
+            let read =
+                CommittedInstance::deserialize_with_mode(&bytes[..], compress, Validate::Yes);
+            assert_eq!(read.unwrap(), ci);
+        }
+
+        // cm_e moved off the curve
+        let mut bytes = Vec::new();
+        ci.serialize_uncompressed(&mut bytes).unwrap();
+        bytes[0] ^= 1;
+        assert!(CommittedInstance::<Projective>::deserialize_uncompressed(&bytes[..]).is_err());
+        assert!(
+            CommittedInstance::<Projective>::deserialize_uncompressed_unchecked(&bytes[..]).is_ok()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_witness_pad_does_not_truncate() {
+        let witness = Witness::<Projective>::new(vec![Fr::from(1u64); 4], 4);
+        witness.pad(2, 4);
+    }
+}
@@ -4,7 +4,7 @@ use ark_ec::CurveGroup;
 use ark_std::One;
 
 use crate::{
-    ccs::r1cs::{hadamard, scalar_mul_vec, vec_add_vec, vec_mul_matrix, vec_sub_vec, R1CS},
+    ccs::r1cs::{hadamard, scalar_mul_vec, vec_add_vec, vec_sub_vec, PreparedR1CS},
     pedersen::{Params as PedersenParams, Pedersen, Proof as PedersenProof},
     transcript::Transcript,
 };
@@ -16,8 +16,9 @@ pub struct NIFS<C: CurveGroup> {
 }
 
 impl<C: CurveGroup> NIFS<C> {
+    /// r1cs is prepared once by the caller, so the matrices are not converted at every fold
     pub fn compute_t(
-        r1cs: &R1CS<C::ScalarField>,
+        r1cs: &PreparedR1CS<C::ScalarField>,
         u1: C::ScalarField,
         u2: C::ScalarField,
         z1: &[C::ScalarField],
@@ -25,12 +26,12 @@ impl<C: CurveGroup> NIFS<C> {
     ) -> Vec<C::ScalarField> {
         let (a, b, c) = (&r1cs.a, &r1cs.b, &r1cs.c);
 
-        let az1 = vec_mul_matrix(z1, a);
-        let az2 = vec_mul_matrix(z2, a);
-        let bz1 = vec_mul_matrix(z1, b);
-        let bz2 = vec_mul_matrix(z2, b);
-        let cz1 = vec_mul_matrix(z1, c);
-        let cz2 = vec_mul_matrix(z2, c);
+        let az1 = a.mul_vec(z1);
+        let az2 = a.mul_vec(z2);
+        let bz1 = b.mul_vec(z1);
+        let bz2 = b.mul_vec(z2);
+        let cz1 = c.mul_vec(z1);
+        let cz2 = c.mul_vec(z2);
 
         let az1_bz2 = hadamard(&az1, &bz2);
         let az2_bz1 = hadamard(&az2, &bz1);
@@ -79,7 +80,7 @@ impl<C: CurveGroup> NIFS<C> {
     pub fn prove(
         params: &PedersenParams<C>,
         r: C::ScalarField,
-        r1cs: &R1CS<C::ScalarField>,
+        r1cs: &PreparedR1CS<C::ScalarField>,
         w1: &Witness<C>,
         ci1: &CommittedInstance<C>,
         w2: &Witness<C>,
@@ -106,7 +107,7 @@ impl<C: CurveGroup> NIFS<C> {
         ci2: &CommittedInstance<C>,
         cm_t: &C,
     ) -> CommittedInstance<C> {
-        Self::fold_committed_instance(r, cm_t, &ci1, &ci2)
+        Self::fold_committed_instance(r, cm_t, ci1, ci2)
     }
 
     ///Just verify fold method
@@ -134,7 +135,7 @@ impl<C: CurveGroup> NIFS<C> {
             return false;
         }
 
-        return true;
+        true
     }
 
     /// use pedersen commitment to getnerate proof
@@ -143,7 +144,7 @@ impl<C: CurveGroup> NIFS<C> {
         params: &PedersenParams<C>,
         w: &Witness<C>,
         ci: &CommittedInstance<C>,
-        t: &Vec<C::ScalarField>,
+        t: &[C::ScalarField],
         cm_t: &C,
     ) -> (PedersenProof<C>, PedersenProof<C>, PedersenProof<C>) {
         let cm_w_proof = Pedersen::prove(&ci.cm_w, &w.w, &w.r_w, params, ts);
@@ -171,7 +172,7 @@ impl<C: CurveGroup> NIFS<C> {
         if !Pedersen::verify(cm_t, cm_t_proof, params, ts) {
             return false;
         }
-        return true;
+        true
     }
 }
 
@@ -181,7 +182,11 @@ mod tests {
     use ark_pallas::{Fr, Projective};
 
     use crate::{
-        ccs::r1cs::tests::{get_test_r1cs, get_test_z},
+        ccs::builder::tests::get_test_degree_5_ccs,
+        ccs::r1cs::{
+            tests::{get_test_r1cs, get_test_r1cs_from_circuit, get_test_z},
+            to_f_vec, vec_mul_matrix, R1CS,
+        },
         transcript::poseidon::{tests::poseidon_test_config, PoseidonTranscript},
     };
     use ark_std::UniformRand;
@@ -189,10 +194,10 @@ mod tests {
     use super::*;
 
     pub fn check_relaxed_r1cs<F: PrimeField>(r1cs: &R1CS<F>, z: Vec<F>, u: F, e: &[F]) {
-        let az = vec_mul_matrix(&z, &r1cs.a);
-        let bz = vec_mul_matrix(&z, &r1cs.b);
-        let cz = vec_mul_matrix(&z, &r1cs.c);
-        assert!(hadamard(&az, &bz) == vec_add_vec(&e, &scalar_mul_vec(u, &cz)));
+        let az = vec_mul_matrix(&z, &r1cs.a).unwrap();
+        let bz = vec_mul_matrix(&z, &r1cs.b).unwrap();
+        let cz = vec_mul_matrix(&z, &r1cs.c).unwrap();
+        assert!(hadamard(&az, &bz) == vec_add_vec(e, &scalar_mul_vec(u, &cz)));
     }
 
     #[test]
@@ -212,7 +217,8 @@ mod tests {
         let ci1 = w1.commit(&params, x1);
         let ci2 = w2.commit(&params, x2);
 
-        let (w, _, t, cm_t) = NIFS::prove(&params, r, &r1cs, &w1, &ci1, &w2, &ci2);
+        let (w, _, t, cm_t) =
+            NIFS::prove(&params, r, &r1cs.prepare().unwrap(), &w1, &ci1, &w2, &ci2);
         //nifs verify
         let ci = NIFS::verify(r, &ci1, &ci2, &cm_t);
 
@@ -250,6 +256,7 @@ mod tests {
     #[test]
     fn test_nifs_fold_loop() {
         let r1cs = get_test_r1cs();
+        let prepared = r1cs.prepare().unwrap();
         let mut z1 = get_test_z(3);
         let (w1, x1) = r1cs.split_z(&z1);
 
@@ -259,7 +266,7 @@ mod tests {
         let mut w1 = Witness::<Projective>::new(w1.clone(), r1cs.a.n_rows);
         let mut ci1 = w1.commit(&params, x1);
         let mut t1 = Vec::new();
-        let mut cm_t1 = ci1.cm_w.clone();
+        let mut cm_t1 = ci1.cm_w;
         check_relaxed_r1cs(&r1cs, z1.clone(), ci1.u, &w1.e);
 
         let n = 10;
@@ -271,7 +278,7 @@ mod tests {
             check_relaxed_r1cs(&r1cs, z2.clone(), ci2.u, &w2.e);
 
             let r = Fr::rand(&mut rng);
-            let (w3, _, t, cm_t) = NIFS::prove(&params, r, &r1cs, &w1, &ci1, &w2, &ci2);
+            let (w3, _, t, cm_t) = NIFS::prove(&params, r, &prepared, &w1, &ci1, &w2, &ci2);
             //nifs verify
             let ci3 = NIFS::verify(r, &ci1, &ci2, &cm_t);
             //
@@ -308,4 +315,118 @@ mod tests {
         );
         assert!(v);
     }
+
+    #[test]
+    fn test_nifs_fold_constraint_system() {
+        let (r1cs, z1) = get_test_r1cs_from_circuit::<Fr>(3);
+        let (_, z2) = get_test_r1cs_from_circuit::<Fr>(4);
+        let (w1, x1) = r1cs.split_z(&z1);
+        let (w2, x2) = r1cs.split_z(&z2);
+        let w1 = Witness::<Projective>::new(w1, r1cs.a.n_rows);
+        let w2 = Witness::new(w2, r1cs.a.n_rows);
+
+        let mut rng = ark_std::test_rng();
+        let params = Pedersen::new_params(&mut rng, r1cs.a.n_cols);
+        let ci1 = w1.commit(&params, x1);
+        let ci2 = w2.commit(&params, x2);
+
+        let r = Fr::rand(&mut rng);
+        let (w, _, _, cm_t) =
+            NIFS::prove(&params, r, &r1cs.prepare().unwrap(), &w1, &ci1, &w2, &ci2);
+        let ci = NIFS::verify(r, &ci1, &ci2, &cm_t);
+        let z = [vec![ci.u], ci.x.to_vec(), w.w.to_vec()].concat();
+        check_relaxed_r1cs(&r1cs, z, ci.u, &w.e);
+        assert!(NIFS::verify_fold_instance(r, &ci, &ci1, &ci2, &cm_t));
+    }
+
+    #[test]
+    fn test_nifs_fold_padded() {
+        let r1cs = get_test_r1cs::<Fr>();
+        let padded = r1cs.pad();
+        let mut rng = ark_std::test_rng();
+        let params = Pedersen::new_params(&mut rng, padded.a.n_cols);
+
+        let (w1, x1) = r1cs.split_z(&get_test_z(3));
+        let w1 = Witness::<Projective>::new(w1, r1cs.a.n_rows);
+        let ci1 = w1.commit(&params, x1);
+        // padding with zeros keeps the commitments
+        let (w_len, e_len) = (padded.a.n_cols - padded.l - 1, padded.a.n_rows);
+        let w1 = w1.pad(w_len, e_len);
+        assert_eq!(w1.commit(&params, ci1.x.clone()), ci1);
+
+        let (w2, x2) = padded.split_z(&padded.pad_z(&get_test_z(4)));
+        let w2 = Witness::<Projective>::new(w2, e_len);
+        let ci2 = w2.commit(&params, x2);
+
+        let r = Fr::rand(&mut rng);
+        let (w, _, _, cm_t) =
+            NIFS::prove(&params, r, &padded.prepare().unwrap(), &w1, &ci1, &w2, &ci2);
+        let ci = NIFS::verify(r, &ci1, &ci2, &cm_t);
+        let z = [vec![ci.u], ci.x.to_vec(), w.w.to_vec()].concat();
+        check_relaxed_r1cs(&padded, z, ci.u, &w.e);
+        assert!(NIFS::verify_fold_instance(r, &ci, &ci1, &ci2, &cm_t));
+    }
+
+    #[test]
+    fn test_nifs_fold_ccs_to_r1cs() {
+        let ccs = get_test_degree_5_ccs::<Fr>();
+        let r1cs = ccs.to_r1cs();
+        let z1 = ccs.extend_z(&to_f_vec(vec![1, 35, 2, 3, 246]));
+        let z2 = ccs.extend_z(&to_f_vec(vec![1, 246, 3, 2, 35]));
+        let (w1, x1) = r1cs.split_z(&z1);
+        let (w2, x2) = r1cs.split_z(&z2);
+        let w1 = Witness::<Projective>::new(w1, r1cs.a.n_rows);
+        let w2 = Witness::new(w2, r1cs.a.n_rows);
+
+        let mut rng = ark_std::test_rng();
+        let params = Pedersen::new_params(&mut rng, r1cs.a.n_cols);
+        let ci1 = w1.commit(&params, x1);
+        let ci2 = w2.commit(&params, x2);
+
+        let r = Fr::rand(&mut rng);
+        let (w, _, _, cm_t) =
+            NIFS::prove(&params, r, &r1cs.prepare().unwrap(), &w1, &ci1, &w2, &ci2);
+        let ci = NIFS::verify(r, &ci1, &ci2, &cm_t);
+        let z = [vec![ci.u], ci.x.to_vec(), w.w.to_vec()].concat();
+        check_relaxed_r1cs(&r1cs, z, ci.u, &w.e);
+        assert!(NIFS::verify_fold_instance(r, &ci, &ci1, &ci2, &cm_t));
+    }
+
+    #[test]
+    fn test_nifs_fold_random_r1cs() {
+        use crate::ccs::random::tests::sample_test_shapes;
+        use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+        for (seed, shape) in sample_test_shapes(2, 8).iter().enumerate() {
+            let mut rng = StdRng::seed_from_u64(seed as u64);
+            let (r1cs, z2) = R1CS::<Fr>::random(&mut rng, shape).unwrap();
+            let params = Pedersen::<Projective>::new_params(&mut rng, shape.m.max(shape.n));
+
+            // any z is a relaxed instance with u = z_0 and e = Az o Bz - u * Cz
+            let z1: Vec<Fr> = (0..shape.n).map(|_| Fr::rand(&mut rng)).collect();
+            let prepared = r1cs.prepare().unwrap();
+            let cz1 = prepared.c.mul_vec(&z1);
+            let e1 = vec_sub_vec(
+                &hadamard(&prepared.a.mul_vec(&z1), &prepared.b.mul_vec(&z1)),
+                &scalar_mul_vec(z1[0], &cz1),
+            );
+            let (w1, x1) = r1cs.split_z(&z1);
+            let mut w1 = Witness::<Projective>::new(w1, shape.m);
+            w1.e = e1;
+            let mut ci1 = w1.commit(&params, x1);
+            ci1.u = z1[0];
+            check_relaxed_r1cs(&r1cs, z1, ci1.u, &w1.e);
+
+            let (w2, x2) = r1cs.split_z(&z2);
+            let w2 = Witness::<Projective>::new(w2, shape.m);
+            let ci2 = w2.commit(&params, x2);
+
+            let r = Fr::rand(&mut rng);
+            let (w, _, _, cm_t) = NIFS::prove(&params, r, &prepared, &w1, &ci1, &w2, &ci2);
+            let ci = NIFS::verify(r, &ci1, &ci2, &cm_t);
+            let z = [vec![ci.u], ci.x.to_vec(), w.w.to_vec()].concat();
+            check_relaxed_r1cs(&r1cs, z, ci.u, &w.e);
+            assert!(NIFS::verify_fold_instance(r, &ci, &ci1, &ci2, &cm_t));
+        }
+    }
 }
@@ -0,0 +1,156 @@
+use ark_ff::PrimeField;
+use ark_std::log2;
+
+use super::Error;
+use crate::utils::mle::eq_evals;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GateType {
+    Add,
+    Mul,
+}
+
+/// A gate reading two values of the next layer (towards the inputs)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Gate {
+    pub gate_type: GateType,
+    pub left: usize,
+    pub right: usize,
+}
+
+impl Gate {
+    pub fn add(left: usize, right: usize) -> Self {
+        Gate {
+            gate_type: GateType::Add,
+            left,
+            right,
+        }
+    }
+
+    pub fn mul(left: usize, right: usize) -> Self {
+        Gate {
+            gate_type: GateType::Mul,
+            left,
+            right,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayeredCircuit {
+    // layers[0] is the output layer, the last layer reads the inputs
+    pub layers: Vec<Vec<Gate>>,
+    pub num_inputs: usize,
+}
+
+impl LayeredCircuit {
+    pub fn new(layers: Vec<Vec<Gate>>, num_inputs: usize) -> Result<Self, Error> {
+        for (i, layer) in layers.iter().enumerate() {
+            let next_len = layers.get(i + 1).map_or(num_inputs, |l| l.len());
+            for gate in layer {
+                if gate.left >= next_len || gate.right >= next_len {
+                    return Err(Error::InvalidWire(i, gate.left.max(gate.right), next_len));
+                }
+            }
+        }
+        Ok(Self { layers, num_inputs })
+    }
+
+    pub fn depth(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// number of variables of the MLE of layer i, the inputs being layer depth()
+    pub fn num_vars(&self, i: usize) -> usize {
+        let len = self.layers.get(i).map_or(self.num_inputs, |l| l.len());
+        log2(len.max(1)) as usize
+    }
+
+    /// values of every layer, from the outputs down to the inputs
+    pub fn evaluate<F: PrimeField>(&self, inputs: &[F]) -> Result<Vec<Vec<F>>, Error> {
+        if inputs.len() != self.num_inputs {
+            return Err(Error::InvalidInputLength(inputs.len(), self.num_inputs));
+        }
+        let mut values = vec![inputs.to_vec()];
+        for layer in self.layers.iter().rev() {
+            let next = values.last().unwrap();
+            let v = layer
+                .iter()
+                .map(|g| match g.gate_type {
+                    GateType::Add => next[g.left] + next[g.right],
+                    GateType::Mul => next[g.left] * next[g.right],
+                })
+                .collect();
+            values.push(v);
+        }
+        values.reverse();
+        Ok(values)
+    }
+
+    /// Evaluates the wiring predicates add~(g, b, c) and mul~(g, b, c) of layer i,
+    /// folded over g with the given weights, at (r_b, r_c). Linear in the gate count.
+    pub fn wiring_eval<F: PrimeField>(
+        &self,
+        i: usize,
+        weights: &[F],
+        r_b: &[F],
+        r_c: &[F],
+    ) -> (F, F) {
+        let eq_b = eq_evals(r_b);
+        let eq_c = eq_evals(r_c);
+        let mut add = F::zero();
+        let mut mul = F::zero();
+        for (g, gate) in self.layers[i].iter().enumerate() {
+            let v = weights[g] * eq_b[gate.left] * eq_c[gate.right];
+            match gate.gate_type {
+                GateType::Add => add += v,
+                GateType::Mul => mul += v,
+            }
+        }
+        (add, mul)
+    }
+
+    /// Tables over b of the first sum-check phase, with V = V_{i+1} padded to 2^k:
+    /// sum_c f(b, c) = V(b) * h(b) + h_add(b) where h(b) = sum_c add(b, c) + mul(b, c) * V(c)
+    /// and h_add(b) = sum_c add(b, c) * V(c). Linear in the gate count.
+    pub fn phase_one_tables<F: PrimeField>(
+        &self,
+        i: usize,
+        weights: &[F],
+        next: &[F],
+    ) -> (Vec<F>, Vec<F>) {
+        let k = self.num_vars(i + 1);
+        let mut h = vec![F::zero(); 1 << k];
+        let mut h_add = vec![F::zero(); 1 << k];
+        for (g, gate) in self.layers[i].iter().enumerate() {
+            match gate.gate_type {
+                GateType::Add => {
+                    h[gate.left] += weights[g];
+                    h_add[gate.left] += weights[g] * next[gate.right];
+                }
+                GateType::Mul => h[gate.left] += weights[g] * next[gate.right],
+            }
+        }
+        (h, h_add)
+    }
+
+    /// Tables over c of the second phase, add(r_b, c) and mul(r_b, c), given eq(r_b, .)
+    pub fn phase_two_tables<F: PrimeField>(
+        &self,
+        i: usize,
+        weights: &[F],
+        eq_b: &[F],
+    ) -> (Vec<F>, Vec<F>) {
+        let k = self.num_vars(i + 1);
+        let mut add = vec![F::zero(); 1 << k];
+        let mut mul = vec![F::zero(); 1 << k];
+        for (g, gate) in self.layers[i].iter().enumerate() {
+            let v = weights[g] * eq_b[gate.left];
+            match gate.gate_type {
+                GateType::Add => add[gate.right] += v,
+                GateType::Mul => mul[gate.right] += v,
+            }
+        }
+        (add, mul)
+    }
+}
@@ -0,0 +1,333 @@
+use ark_ec::CurveGroup;
+use ark_ff::PrimeField;
+use ark_poly::MultilinearExtension;
+use ark_std::{One, Zero};
+use std::marker::PhantomData;
+use thiserror::Error;
+
+use crate::pcs::{Error as PCSError, MultilinearPCS};
+use crate::transcript::Transcript;
+use crate::utils::mle::{eq_eval, eq_evals, vec_to_mle};
+use crate::utils::sum_check::{Error as SumCheckError, SumCheck, SumCheckProof, VirtualPolynomial};
+
+pub mod circuit;
+use circuit::*;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Layer {0}: gate reads wire {1} but the next layer has {2} values")]
+    InvalidWire(usize, usize, usize),
+    #[error("Got {0} inputs, expected {1}")]
+    InvalidInputLength(usize, usize),
+    #[error("Got {0} outputs, expected {1}")]
+    InvalidOutputLength(usize, usize),
+    #[error("Expected {0} layer proofs, got {1}")]
+    InvalidNumLayers(usize, usize),
+    #[error(transparent)]
+    SumCheck(#[from] SumCheckError),
+    #[error(transparent)]
+    PCS(#[from] PCSError),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayerProof<F: PrimeField> {
+    pub sum_check: SumCheckProof<F>,
+    // V_{i+1}(r_b), V_{i+1}(r_c)
+    pub v_b: F,
+    pub v_c: F,
+}
+
+pub struct GKRProof<C: CurveGroup, P: MultilinearPCS<C>> {
+    pub layers: Vec<LayerProof<C::ScalarField>>,
+    // reduces the two input claims to a single opening of the committed inputs
+    pub input_sum_check: SumCheckProof<C::ScalarField>,
+    pub input_value: C::ScalarField,
+    pub input_opening: P::Proof,
+}
+
+/// GKR for layered arithmetic circuits, every layer i is reduced to layer i + 1 with
+/// V_i(g) = sum_{b,c} add(g,b,c) * (V_{i+1}(b) + V_{i+1}(c)) + mul(g,b,c) * V_{i+1}(b) * V_{i+1}(c)
+/// and the two resulting claims are folded into the next layer with random weights.
+/// The prover binds b then c (Libra's two phases) with tables built from the gates, so
+/// a layer costs O(gates + 2^k) rather than O(2^{2k}).
+pub struct GKR<C: CurveGroup, P: MultilinearPCS<C>> {
+    _c: PhantomData<C>,
+    _p: PhantomData<P>,
+}
+
+impl<C: CurveGroup, P: MultilinearPCS<C>> GKR<C, P> {
+    pub fn commit_inputs(
+        pp: &P::ProverParams,
+        circuit: &LayeredCircuit,
+        inputs: &[C::ScalarField],
+    ) -> Result<P::Commitment, Error> {
+        Ok(P::commit(
+            pp,
+            &vec_to_mle(circuit.num_vars(circuit.depth()), inputs),
+        )?)
+    }
+
+    /// Returns the outputs of the circuit and the proof that they are computed
+    /// from the inputs committed in cm.
+    #[allow(clippy::type_complexity)]
+    pub fn prove(
+        pp: &P::ProverParams,
+        circuit: &LayeredCircuit,
+        inputs: &[C::ScalarField],
+        cm: &P::Commitment,
+        transcript: &mut impl Transcript<C>,
+    ) -> Result<(Vec<C::ScalarField>, GKRProof<C, P>), Error> {
+        let values = circuit.evaluate(inputs)?;
+        let outputs = values[0].clone();
+        P::absorb_commitment(transcript, cm);
+        transcript.absorb_vec(&outputs);
+
+        let r_0 = transcript.get_challenges(circuit.num_vars(0));
+        // the output claim is eq(r_0, .) = combine_eq(1, r_0, 0, r_0)
+        let (mut alpha, mut beta) = (C::ScalarField::one(), C::ScalarField::zero());
+        let (mut r_b, mut r_c) = (r_0.clone(), r_0);
+        let mut weights = combine_eq(alpha, &r_b, beta, &r_c);
+        let mut layers = Vec::with_capacity(circuit.depth());
+        for i in 0..circuit.depth() {
+            let k = circuit.num_vars(i + 1);
+            let next = &values[i + 1];
+            let next_mle = vec_to_mle(k, next);
+            let one = C::ScalarField::one();
+
+            // phase 1 binds b, summing c out through the gates
+            let (h, h_add) = circuit.phase_one_tables(i, &weights, &next_mle.evaluations);
+            let mut poly = VirtualPolynomial::new(k);
+            let v = poly.add_mle(next_mle.clone());
+            let h = poly.add_mle(vec_to_mle(k, &h));
+            let h_add = poly.add_mle(vec_to_mle(k, &h_add));
+            poly.add_product(one, vec![v, h]);
+            poly.add_product(one, vec![h_add]);
+            let (phase_one, point) = SumCheck::<C::ScalarField>::prove(&poly, transcript);
+            r_b = point;
+            let v_b = next_mle.evaluate(&r_b).unwrap();
+
+            // phase 2 binds c in f(r_b, c) = add(c) * (v_b + V(c)) + mul(c) * v_b * V(c)
+            let (add, mul) = circuit.phase_two_tables(i, &weights, &eq_evals(&r_b));
+            let mut poly = VirtualPolynomial::new(k);
+            let v = poly.add_mle(next_mle.clone());
+            let add = poly.add_mle(vec_to_mle(k, &add));
+            let mul = poly.add_mle(vec_to_mle(k, &mul));
+            poly.add_product(v_b, vec![add]);
+            poly.add_product(one, vec![add, v]);
+            poly.add_product(v_b, vec![mul, v]);
+            let (phase_two, point) = SumCheck::<C::ScalarField>::prove(&poly, transcript);
+            r_c = point;
+
+            // the rounds of both phases are those of one sum-check over (b, c)
+            let sum_check = SumCheckProof {
+                round_polys: [phase_one.round_polys, phase_two.round_polys].concat(),
+            };
+            let layer = LayerProof {
+                sum_check,
+                v_b,
+                v_c: next_mle.evaluate(&r_c).unwrap(),
+            };
+            transcript.absorb(&layer.v_b);
+            transcript.absorb(&layer.v_c);
+            alpha = transcript.get_challenge();
+            beta = transcript.get_challenge();
+            weights = combine_eq(alpha, &r_b, beta, &r_c);
+            layers.push(layer);
+        }
+
+        // sum_x (alpha * eq(r_b, x) + beta * eq(r_c, x)) * W(x) = alpha * W(r_b) + beta * W(r_c)
+        let n = circuit.num_vars(circuit.depth());
+        let mut poly = VirtualPolynomial::new(n);
+        let w = poly.add_mle(vec_to_mle(n, &weights));
+        let input = poly.add_mle(vec_to_mle(n, inputs));
+        poly.add_product(C::ScalarField::one(), vec![w, input]);
+        let (input_sum_check, point) = SumCheck::prove(&poly, transcript);
+        let (input_opening, input_value) = P::open(pp, &poly.mles[input], cm, &point, transcript)?;
+        let proof = GKRProof {
+            layers,
+            input_sum_check,
+            input_value,
+            input_opening,
+        };
+        Ok((outputs, proof))
+    }
+
+    pub fn verify(
+        vp: &P::VerifierParams,
+        circuit: &LayeredCircuit,
+        outputs: &[C::ScalarField],
+        cm: &P::Commitment,
+        proof: &GKRProof<C, P>,
+        transcript: &mut impl Transcript<C>,
+    ) -> Result<bool, Error> {
+        let n_out = circuit
+            .layers
+            .first()
+            .map_or(circuit.num_inputs, |l| l.len());
+        if outputs.len() != n_out {
+            return Err(Error::InvalidOutputLength(outputs.len(), n_out));
+        }
+        if proof.layers.len() != circuit.depth() {
+            return Err(Error::InvalidNumLayers(circuit.depth(), proof.layers.len()));
+        }
+        P::absorb_commitment(transcript, cm);
+        transcript.absorb_vec(outputs);
+
+        let r_0 = transcript.get_challenges(circuit.num_vars(0));
+        let mut claim = vec_to_mle(circuit.num_vars(0), outputs)
+            .evaluate(&r_0)
+            .unwrap();
+        let (mut alpha, mut beta) = (C::ScalarField::one(), C::ScalarField::zero());
+        let (mut r_b, mut r_c) = (r_0.clone(), r_0);
+        let mut weights = combine_eq(alpha, &r_b, beta, &r_c);
+        for (i, layer) in proof.layers.iter().enumerate() {
+            let k = circuit.num_vars(i + 1);
+            let (expected, point) =
+                SumCheck::verify(claim, &layer.sum_check, 2 * k, 2, transcript)?;
+            r_b = point[..k].to_vec();
+            r_c = point[k..].to_vec();
+            let (add, mul) = circuit.wiring_eval(i, &weights, &r_b, &r_c);
+            if expected != add * (layer.v_b + layer.v_c) + mul * layer.v_b * layer.v_c {
+                return Ok(false);
+            }
+            transcript.absorb(&layer.v_b);
+            transcript.absorb(&layer.v_c);
+            alpha = transcript.get_challenge();
+            beta = transcript.get_challenge();
+            claim = alpha * layer.v_b + beta * layer.v_c;
+            weights = combine_eq(alpha, &r_b, beta, &r_c);
+        }
+
+        let n = circuit.num_vars(circuit.depth());
+        let (expected, point) = SumCheck::verify(claim, &proof.input_sum_check, n, 2, transcript)?;
+        let w = alpha * eq_eval(&r_b, &point) + beta * eq_eval(&r_c, &point);
+        if expected != w * proof.input_value {
+            return Ok(false);
+        }
+        Ok(P::verify(
+            vp,
+            cm,
+            &point,
+            &proof.input_value,
+            &proof.input_opening,
+            transcript,
+        )?)
+    }
+}
+
+// alpha * eq(r_b, .) + beta * eq(r_c, .)
+fn combine_eq<F: PrimeField>(alpha: F, r_b: &[F], beta: F, r_c: &[F]) -> Vec<F> {
+    eq_evals(r_b)
+        .iter()
+        .zip(eq_evals(r_c))
+        .map(|(b, c)| alpha * b + beta * c)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bn254::{Bn254, Fr, G1Projective};
+    use ark_std::UniformRand;
+
+    use super::*;
+    use crate::pcs::pst::PST;
+    use crate::transcript::poseidon::{tests::poseidon_test_config, PoseidonTranscript};
+
+    type TestGKR = GKR<G1Projective, PST<Bn254>>;
+
+    // product of two 2x2 matrices, inputs are (a00, a01, a10, a11, b00, b01, b10, b11)
+    fn matrix_product_circuit() -> LayeredCircuit {
+        let mut muls = Vec::new();
+        for i in 0..2 {
+            for j in 0..2 {
+                for k in 0..2 {
+                    muls.push(Gate::mul(2 * i + k, 4 + 2 * k + j));
+                }
+            }
+        }
+        let adds = (0..4).map(|g| Gate::add(2 * g, 2 * g + 1)).collect();
+        LayeredCircuit::new(vec![adds, muls], 8).unwrap()
+    }
+
+    // x -> x^2 + x applied n times to each input, the last input is a zero wire
+    // carried along for the pass-through gates
+    fn chain_circuit(n: usize, width: usize) -> LayeredCircuit {
+        let zero = std::iter::once;
+        let mut layers = Vec::new();
+        for _ in 0..n {
+            layers.push(
+                (0..width)
+                    .map(|g| Gate::add(2 * g, 2 * g + 1))
+                    .chain(zero(Gate::add(2 * width, 2 * width)))
+                    .collect(),
+            );
+            layers.push(
+                (0..width)
+                    .flat_map(|g| [Gate::mul(g, g), Gate::add(g, width)])
+                    .chain(zero(Gate::mul(width, width)))
+                    .collect(),
+            );
+        }
+        LayeredCircuit::new(layers, width + 1).unwrap()
+    }
+
+    fn check_gkr(circuit: &LayeredCircuit, inputs: &[Fr]) -> Vec<Fr> {
+        let mut rng = ark_std::test_rng();
+        let (pp, vp) = PST::<Bn254>::setup(&mut rng, circuit.num_vars(circuit.depth()));
+        let config = poseidon_test_config::<Fr>();
+        let cm = TestGKR::commit_inputs(&pp, circuit, inputs).unwrap();
+
+        let mut ts_prove = PoseidonTranscript::<G1Projective>::new(&config);
+        let (outputs, proof) = TestGKR::prove(&pp, circuit, inputs, &cm, &mut ts_prove).unwrap();
+        let mut ts_verify = PoseidonTranscript::<G1Projective>::new(&config);
+        assert!(TestGKR::verify(&vp, circuit, &outputs, &cm, &proof, &mut ts_verify).unwrap());
+
+        let mut wrong = outputs.clone();
+        wrong[0] += Fr::one();
+        let mut ts_verify = PoseidonTranscript::<G1Projective>::new(&config);
+        assert!(
+            !TestGKR::verify(&vp, circuit, &wrong, &cm, &proof, &mut ts_verify).unwrap_or(false)
+        );
+        outputs
+    }
+
+    #[test]
+    fn test_gkr_matrix_product() {
+        let circuit = matrix_product_circuit();
+        let inputs: Vec<Fr> = [1u64, 2, 3, 4, 5, 6, 7, 8].map(Fr::from).to_vec();
+        let outputs = check_gkr(&circuit, &inputs);
+        assert_eq!(outputs, [19u64, 22, 43, 50].map(Fr::from).to_vec());
+    }
+
+    #[test]
+    fn test_gkr_chain() {
+        let mut rng = ark_std::test_rng();
+        let width = 3;
+        let circuit = chain_circuit(2, width);
+        let mut inputs: Vec<Fr> = (0..width).map(|_| Fr::rand(&mut rng)).collect();
+        inputs.push(Fr::from(0u64));
+        let outputs = check_gkr(&circuit, &inputs);
+        for (x, y) in inputs.iter().zip(outputs) {
+            let x1 = *x * x + x;
+            assert_eq!(y, x1 * x1 + x1);
+        }
+    }
+
+    // a single layer over 2^11 inputs, the dense wiring tables would have 2^22 entries
+    #[test]
+    fn test_gkr_wide_layer() {
+        let mut rng = ark_std::test_rng();
+        let width = 1 << 10;
+        let layer = (0..width)
+            .map(|g| match g % 2 {
+                0 => Gate::mul(2 * g, 2 * g + 1),
+                _ => Gate::add(2 * g, (7 * g) % (2 * width)),
+            })
+            .collect();
+        let circuit = LayeredCircuit::new(vec![layer], 2 * width).unwrap();
+        let inputs: Vec<Fr> = (0..2 * width).map(|_| Fr::rand(&mut rng)).collect();
+        let outputs = check_gkr(&circuit, &inputs);
+        assert_eq!(outputs, circuit.evaluate(&inputs).unwrap()[0]);
+        assert_eq!(outputs[0], inputs[0] * inputs[1]);
+    }
+}
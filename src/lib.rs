@@ -1,7 +1,11 @@
 pub mod ccs;
+pub mod frontend;
 pub mod fs;
+pub mod gkr;
+pub mod pcs;
 pub mod pedersen;
 pub mod transcript;
+pub mod utils;
 
 
 
@@ -0,0 +1,636 @@
+use ark_crypto_primitives::sponge::poseidon::PoseidonConfig;
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ec::CurveGroup;
+use ark_ff::{FftField, Field, PrimeField};
+use ark_poly::{
+    DenseMultilinearExtension, EvaluationDomain, MultilinearExtension, Radix2EvaluationDomain,
+};
+use ark_std::{One, Zero};
+use std::marker::PhantomData;
+
+use super::{Error, MultilinearPCS};
+use crate::transcript::Transcript;
+use crate::utils::merkle::{poseidon_hash, MerkleTree};
+use crate::utils::mle::{eq_eval, eq_evals};
+use crate::utils::sum_check::interpolate_uni_poly;
+
+#[derive(Clone, Debug)]
+pub struct Params<F: PrimeField> {
+    pub poseidon_config: PoseidonConfig<F>,
+    // the codeword is 2^rate_bits times longer than the message
+    pub rate_bits: usize,
+    pub num_queries: usize,
+}
+
+impl<F: PrimeField> Params<F> {
+    pub fn new(poseidon_config: PoseidonConfig<F>, rate_bits: usize, num_queries: usize) -> Self {
+        assert!(rate_bits >= 1);
+        Self {
+            poseidon_config,
+            rate_bits,
+            num_queries,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleOpening<F: PrimeField> {
+    // codeword values at x and -x
+    pub left: F,
+    pub right: F,
+    pub path: Vec<F>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryProof<F: PrimeField> {
+    // one opening per committed polynomial
+    pub first: Vec<MerkleOpening<F>>,
+    // openings of the folded codewords 1..num_vars
+    pub layers: Vec<MerkleOpening<F>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Proof<F: PrimeField> {
+    // sum-check of eq(point, x) * f(x), evaluations at 0, 1, 2
+    pub round_polys: Vec<Vec<F>>,
+    // roots of the folded codewords 1..num_vars
+    pub roots: Vec<F>,
+    // the fully folded codeword is the constant f(challenges)
+    pub final_value: F,
+    pub queries: Vec<QueryProof<F>>,
+}
+
+/// Basefold multilinear commitment: the evaluation table of f is used as the coefficients
+/// of a univariate polynomial, Reed-Solomon encoded over a 2-adic subgroup and committed
+/// with a Poseidon Merkle tree. The opening runs the sum-check of eq(r, x) * f(x) while
+/// folding the codeword with the same challenges, FRI style, so the fully folded codeword
+/// is the constant f(challenges). Needs no trusted setup and no discrete-log assumption.
+pub struct Basefold<C: CurveGroup> {
+    _c: PhantomData<C>,
+}
+
+struct Encoded<F: PrimeField> {
+    codeword: Vec<F>,
+    tree: MerkleTree<F>,
+}
+
+impl<C: CurveGroup> Basefold<C>
+where
+    C::ScalarField: Absorb,
+{
+    // the codeword of a polynomial with more variables does not fit in the 2-adic subgroup,
+    // the rate is checked here too as the params fields are public
+    fn max_vars(params: &Params<C::ScalarField>) -> Result<usize, Error> {
+        let two_adicity = C::ScalarField::TWO_ADICITY;
+        (two_adicity as usize)
+            .checked_sub(params.rate_bits)
+            .filter(|_| params.rate_bits >= 1)
+            .ok_or(Error::InvalidRate(params.rate_bits, two_adicity))
+    }
+
+    fn encode(
+        params: &Params<C::ScalarField>,
+        poly: &DenseMultilinearExtension<C::ScalarField>,
+    ) -> Result<Encoded<C::ScalarField>, Error> {
+        let max_vars = Self::max_vars(params)?;
+        if poly.num_vars > max_vars {
+            return Err(Error::TooManyVariables(poly.num_vars, max_vars));
+        }
+        let domain =
+            Radix2EvaluationDomain::<C::ScalarField>::new(1 << (poly.num_vars + params.rate_bits))
+                .unwrap();
+        let codeword = domain.fft(&poly.evaluations);
+        let tree = Self::tree(params, &codeword);
+        Ok(Encoded { codeword, tree })
+    }
+
+    // leaf j holds the codeword values at w^j and -w^j = w^{j + n/2}
+    fn tree(
+        params: &Params<C::ScalarField>,
+        codeword: &[C::ScalarField],
+    ) -> MerkleTree<C::ScalarField> {
+        let half = codeword.len() / 2;
+        let leaves = (0..half)
+            .map(|j| poseidon_hash(&params.poseidon_config, &[codeword[j], codeword[j + half]]))
+            .collect();
+        MerkleTree::new(&params.poseidon_config, leaves)
+    }
+
+    fn open_at(
+        tree: &MerkleTree<C::ScalarField>,
+        codeword: &[C::ScalarField],
+        j: usize,
+    ) -> MerkleOpening<C::ScalarField> {
+        let half = codeword.len() / 2;
+        MerkleOpening {
+            left: codeword[j],
+            right: codeword[j + half],
+            path: tree.open(j),
+        }
+    }
+
+    // leaf j of a tree over half = 2^depth leaves
+    fn check_opening(
+        params: &Params<C::ScalarField>,
+        root: &C::ScalarField,
+        half: usize,
+        j: usize,
+        opening: &MerkleOpening<C::ScalarField>,
+    ) -> bool {
+        let leaf = poseidon_hash(&params.poseidon_config, &[opening.left, opening.right]);
+        let depth = half.trailing_zeros() as usize;
+        MerkleTree::verify(&params.poseidon_config, root, depth, j, leaf, &opening.path)
+    }
+
+    // the statement, then powers of a random gamma to combine the polynomials
+    fn absorb_statement(
+        cms: &[C::ScalarField],
+        point: &[C::ScalarField],
+        values: &[C::ScalarField],
+        transcript: &mut impl Transcript<C>,
+    ) -> Vec<C::ScalarField> {
+        transcript.absorb_vec(cms);
+        transcript.absorb_vec(point);
+        transcript.absorb_vec(values);
+        let gamma = transcript.get_challenge();
+        std::iter::successors(Some(C::ScalarField::one()), |g| Some(*g * gamma))
+            .take(cms.len())
+            .collect()
+    }
+
+    fn query_indices(
+        params: &Params<C::ScalarField>,
+        half: usize,
+        transcript: &mut impl Transcript<C>,
+    ) -> Vec<usize> {
+        transcript
+            .get_challenges(params.num_queries)
+            .iter()
+            .map(|c| c.into_bigint().as_ref()[0] as usize % half)
+            .collect()
+    }
+
+    /// opens sum_i gamma^i * polys[i] at point, cms and values are absorbed first
+    fn prove_combined(
+        params: &Params<C::ScalarField>,
+        polys: &[DenseMultilinearExtension<C::ScalarField>],
+        cms: &[C::ScalarField],
+        point: &[C::ScalarField],
+        values: &[C::ScalarField],
+        transcript: &mut impl Transcript<C>,
+    ) -> Result<Proof<C::ScalarField>, Error> {
+        if polys.len() != cms.len() {
+            return Err(Error::BatchLengthMismatch(polys.len(), cms.len()));
+        }
+        Self::max_vars(params)?;
+        let coeffs = Self::absorb_statement(cms, point, values, transcript);
+        let n = point.len();
+        let encoded = polys
+            .iter()
+            .map(|p| {
+                if p.num_vars != n {
+                    return Err(Error::InvalidPoint(n, p.num_vars));
+                }
+                Self::encode(params, p)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut f = vec![C::ScalarField::zero(); 1 << n];
+        let mut codeword = vec![C::ScalarField::zero(); 1 << (n + params.rate_bits)];
+        for ((p, e), c) in polys.iter().zip(&encoded).zip(&coeffs) {
+            for (f_b, p_b) in f.iter_mut().zip(&p.evaluations) {
+                *f_b += *c * p_b;
+            }
+            for (w_j, e_j) in codeword.iter_mut().zip(&e.codeword) {
+                *w_j += *c * e_j;
+            }
+        }
+        let mut eq = eq_evals(point);
+        let domain = Radix2EvaluationDomain::<C::ScalarField>::new(codeword.len()).unwrap();
+        let mut omega = domain.group_gen;
+
+        let mut round_polys = Vec::with_capacity(n);
+        let mut roots = Vec::with_capacity(n.saturating_sub(1));
+        let mut layers: Vec<Encoded<C::ScalarField>> = Vec::new();
+        for k in 0..n {
+            let half = f.len() / 2;
+            let mut evals = vec![C::ScalarField::zero(); 3];
+            for b in 0..half {
+                for (t, eval) in evals.iter_mut().enumerate() {
+                    let t = C::ScalarField::from(t as u64);
+                    let f_t = f[2 * b] + t * (f[2 * b + 1] - f[2 * b]);
+                    let eq_t = eq[2 * b] + t * (eq[2 * b + 1] - eq[2 * b]);
+                    *eval += f_t * eq_t;
+                }
+            }
+            transcript.absorb_vec(&evals);
+            let alpha = transcript.get_challenge();
+            round_polys.push(evals);
+
+            f = (0..half)
+                .map(|b| f[2 * b] + alpha * (f[2 * b + 1] - f[2 * b]))
+                .collect();
+            eq = (0..half)
+                .map(|b| eq[2 * b] + alpha * (eq[2 * b + 1] - eq[2 * b]))
+                .collect();
+            codeword = fold_codeword(&codeword, omega, alpha);
+            omega.square_in_place();
+            if k + 1 < n {
+                let tree = Self::tree(params, &codeword);
+                transcript.absorb(&tree.root());
+                roots.push(tree.root());
+                layers.push(Encoded {
+                    codeword: codeword.clone(),
+                    tree,
+                });
+            }
+        }
+        let final_value = codeword[0];
+        transcript.absorb(&final_value);
+
+        let half = 1 << (n + params.rate_bits - 1);
+        let queries = Self::query_indices(params, half, transcript)
+            .into_iter()
+            .map(|j0| QueryProof {
+                first: encoded
+                    .iter()
+                    .map(|e| Self::open_at(&e.tree, &e.codeword, j0))
+                    .collect(),
+                layers: layers
+                    .iter()
+                    .map(|e| Self::open_at(&e.tree, &e.codeword, j0 % (e.codeword.len() / 2)))
+                    .collect(),
+            })
+            .collect();
+
+        Ok(Proof {
+            round_polys,
+            roots,
+            final_value,
+            queries,
+        })
+    }
+
+    /// checks that f_i(point) = values[i] for the f_i committed in cms
+    fn verify_combined(
+        params: &Params<C::ScalarField>,
+        cms: &[C::ScalarField],
+        point: &[C::ScalarField],
+        values: &[C::ScalarField],
+        proof: &Proof<C::ScalarField>,
+        transcript: &mut impl Transcript<C>,
+    ) -> Result<bool, Error> {
+        if values.len() != cms.len() {
+            return Err(Error::BatchLengthMismatch(values.len(), cms.len()));
+        }
+        let coeffs = Self::absorb_statement(cms, point, values, transcript);
+        let claim = coeffs.iter().zip(values).map(|(c, v)| *c * v).sum();
+        let n = point.len();
+        let max_vars = Self::max_vars(params)?;
+        if n > max_vars {
+            return Err(Error::TooManyVariables(n, max_vars));
+        }
+        if proof.round_polys.len() != n || proof.roots.len() != n.saturating_sub(1) {
+            return Err(Error::InvalidPoint(n, proof.round_polys.len()));
+        }
+        if proof.queries.len() != params.num_queries {
+            return Ok(false);
+        }
+
+        let mut claim = claim;
+        let mut alphas = Vec::with_capacity(n);
+        for (k, evals) in proof.round_polys.iter().enumerate() {
+            if evals.len() != 3 || evals[0] + evals[1] != claim {
+                return Ok(false);
+            }
+            transcript.absorb_vec(evals);
+            let alpha = transcript.get_challenge();
+            claim = interpolate_uni_poly(evals, alpha);
+            alphas.push(alpha);
+            if k + 1 < n {
+                transcript.absorb(&proof.roots[k]);
+            }
+        }
+        transcript.absorb(&proof.final_value);
+        if claim != eq_eval(point, &alphas) * proof.final_value {
+            return Ok(false);
+        }
+
+        let size = 1 << (n + params.rate_bits);
+        let omega = Radix2EvaluationDomain::<C::ScalarField>::new(size)
+            .unwrap()
+            .group_gen;
+        let indices = Self::query_indices(params, size / 2, transcript);
+        for (j0, query) in indices.into_iter().zip(&proof.queries) {
+            if query.first.len() != cms.len() || query.layers.len() != n.saturating_sub(1) {
+                return Ok(false);
+            }
+            let mut left = C::ScalarField::zero();
+            let mut right = C::ScalarField::zero();
+            for ((cm, opening), c) in cms.iter().zip(&query.first).zip(&coeffs) {
+                if !Self::check_opening(params, cm, size / 2, j0, opening) {
+                    return Ok(false);
+                }
+                left += *c * opening.left;
+                right += *c * opening.right;
+            }
+
+            let mut omega_k = omega;
+            let mut half = size / 2;
+            for (k, alpha) in alphas.iter().enumerate() {
+                let j = j0 % half;
+                let folded = fold(left, right, omega_k.pow([j as u64]), *alpha);
+                half /= 2;
+                let next = if k + 1 < n {
+                    let opening = &query.layers[k];
+                    if !Self::check_opening(params, &proof.roots[k], half, j % half, opening) {
+                        return Ok(false);
+                    }
+                    (left, right) = (opening.left, opening.right);
+                    if j < half {
+                        opening.left
+                    } else {
+                        opening.right
+                    }
+                } else {
+                    proof.final_value
+                };
+                if folded != next {
+                    return Ok(false);
+                }
+                omega_k.square_in_place();
+            }
+            // nothing was folded, the committed codeword itself must be constant
+            if n == 0 && (left != proof.final_value || right != proof.final_value) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+// with p(X) = p_e(X^2) + X * p_o(X^2), returns (1 - alpha) * p_e(x^2) + alpha * p_o(x^2)
+fn fold<F: PrimeField>(p_x: F, p_minus_x: F, x: F, alpha: F) -> F {
+    let two_inv = F::from(2u64).inverse().unwrap();
+    let even = (p_x + p_minus_x) * two_inv;
+    let odd = (p_x - p_minus_x) * two_inv * x.inverse().unwrap();
+    even + alpha * (odd - even)
+}
+
+fn fold_codeword<F: PrimeField>(codeword: &[F], omega: F, alpha: F) -> Vec<F> {
+    let half = codeword.len() / 2;
+    let mut x = F::one();
+    (0..half)
+        .map(|j| {
+            let folded = fold(codeword[j], codeword[j + half], x, alpha);
+            x *= omega;
+            folded
+        })
+        .collect()
+}
+
+impl<C: CurveGroup> MultilinearPCS<C> for Basefold<C>
+where
+    C::ScalarField: Absorb,
+{
+    type ProverParams = Params<C::ScalarField>;
+    type VerifierParams = Params<C::ScalarField>;
+    // Merkle root of the codeword
+    type Commitment = C::ScalarField;
+    type Proof = Proof<C::ScalarField>;
+    type BatchProof = Proof<C::ScalarField>;
+
+    fn absorb_commitment(transcript: &mut impl Transcript<C>, cm: &C::ScalarField) {
+        transcript.absorb(cm);
+    }
+
+    fn commit(
+        pp: &Params<C::ScalarField>,
+        poly: &DenseMultilinearExtension<C::ScalarField>,
+    ) -> Result<C::ScalarField, Error> {
+        Ok(Self::encode(pp, poly)?.tree.root())
+    }
+
+    fn open(
+        pp: &Params<C::ScalarField>,
+        poly: &DenseMultilinearExtension<C::ScalarField>,
+        cm: &C::ScalarField,
+        point: &[C::ScalarField],
+        transcript: &mut impl Transcript<C>,
+    ) -> Result<(Proof<C::ScalarField>, C::ScalarField), Error> {
+        let value = poly
+            .evaluate(point)
+            .ok_or(Error::InvalidPoint(point.len(), poly.num_vars))?;
+        let proof = Self::prove_combined(
+            pp,
+            std::slice::from_ref(poly),
+            std::slice::from_ref(cm),
+            point,
+            &[value],
+            transcript,
+        )?;
+        Ok((proof, value))
+    }
+
+    fn verify(
+        vp: &Params<C::ScalarField>,
+        cm: &C::ScalarField,
+        point: &[C::ScalarField],
+        value: &C::ScalarField,
+        proof: &Proof<C::ScalarField>,
+        transcript: &mut impl Transcript<C>,
+    ) -> Result<bool, Error> {
+        Self::verify_combined(
+            vp,
+            std::slice::from_ref(cm),
+            point,
+            std::slice::from_ref(value),
+            proof,
+            transcript,
+        )
+    }
+
+    fn batch_open(
+        pp: &Params<C::ScalarField>,
+        polys: &[DenseMultilinearExtension<C::ScalarField>],
+        cms: &[C::ScalarField],
+        point: &[C::ScalarField],
+        transcript: &mut impl Transcript<C>,
+    ) -> Result<(Proof<C::ScalarField>, Vec<C::ScalarField>), Error> {
+        let values = polys
+            .iter()
+            .map(|p| {
+                p.evaluate(point)
+                    .ok_or(Error::InvalidPoint(point.len(), p.num_vars))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let proof = Self::prove_combined(pp, polys, cms, point, &values, transcript)?;
+        Ok((proof, values))
+    }
+
+    fn batch_verify(
+        vp: &Params<C::ScalarField>,
+        cms: &[C::ScalarField],
+        point: &[C::ScalarField],
+        values: &[C::ScalarField],
+        proof: &Proof<C::ScalarField>,
+        transcript: &mut impl Transcript<C>,
+    ) -> Result<bool, Error> {
+        Self::verify_combined(vp, cms, point, values, proof, transcript)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_pallas::{Fr, Projective};
+    use ark_std::UniformRand;
+
+    use super::*;
+    use crate::transcript::poseidon::{tests::poseidon_test_config, PoseidonTranscript};
+
+    #[test]
+    fn test_basefold_open() {
+        let mut rng = ark_std::test_rng();
+        let params = Params::new(poseidon_test_config::<Fr>(), 2, 20);
+        let config = poseidon_test_config::<Fr>();
+
+        for num_vars in [0, 1, 5] {
+            let poly = DenseMultilinearExtension::<Fr>::rand(num_vars, &mut rng);
+            let point: Vec<Fr> = (0..num_vars).map(|_| Fr::rand(&mut rng)).collect();
+            let cm = Basefold::<Projective>::commit(&params, &poly).unwrap();
+
+            let mut ts_prove = PoseidonTranscript::<Projective>::new(&config);
+            let (proof, value) =
+                Basefold::open(&params, &poly, &cm, &point, &mut ts_prove).unwrap();
+            assert_eq!(value, poly.evaluate(&point).unwrap());
+            let mut ts_verify = PoseidonTranscript::<Projective>::new(&config);
+            assert!(
+                Basefold::verify(&params, &cm, &point, &value, &proof, &mut ts_verify).unwrap()
+            );
+
+            let mut ts_verify = PoseidonTranscript::<Projective>::new(&config);
+            let wrong = value + Fr::one();
+            assert!(
+                !Basefold::verify(&params, &cm, &point, &wrong, &proof, &mut ts_verify).unwrap()
+            );
+
+            // the proof is bound to the point, whose coordinates are absorbed
+            if num_vars > 0 {
+                let mut other_point = point.clone();
+                other_point[0] += Fr::one();
+                let mut ts_verify = PoseidonTranscript::<Projective>::new(&config);
+                assert!(!Basefold::verify(
+                    &params,
+                    &cm,
+                    &other_point,
+                    &value,
+                    &proof,
+                    &mut ts_verify
+                )
+                .unwrap());
+            }
+
+            // a proof for another polynomial does not verify against cm
+            let other = DenseMultilinearExtension::<Fr>::rand(num_vars, &mut rng);
+            let cm_other = Basefold::<Projective>::commit(&params, &other).unwrap();
+            let mut ts_prove = PoseidonTranscript::<Projective>::new(&config);
+            let (proof, value) =
+                Basefold::open(&params, &other, &cm_other, &point, &mut ts_prove).unwrap();
+            let mut ts_verify = PoseidonTranscript::<Projective>::new(&config);
+            assert!(
+                !Basefold::verify(&params, &cm, &point, &value, &proof, &mut ts_verify).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_basefold_invalid_rate() {
+        let mut rng = ark_std::test_rng();
+        // the pallas scalar field has two-adicity 32
+        let params = Params::new(poseidon_test_config::<Fr>(), 33, 20);
+        let poly = DenseMultilinearExtension::<Fr>::rand(1, &mut rng);
+        assert!(matches!(
+            Basefold::<Projective>::commit(&params, &poly),
+            Err(Error::InvalidRate(33, 32))
+        ));
+        let config = poseidon_test_config::<Fr>();
+        let mut ts_verify = PoseidonTranscript::<Projective>::new(&config);
+        let proof = Proof {
+            round_polys: vec![],
+            roots: vec![],
+            final_value: Fr::zero(),
+            queries: vec![],
+        };
+        assert!(matches!(
+            Basefold::verify(
+                &params,
+                &Fr::zero(),
+                &[],
+                &Fr::zero(),
+                &proof,
+                &mut ts_verify
+            ),
+            Err(Error::InvalidRate(33, 32))
+        ));
+
+        // a zero rate can only come from a struct literal, it is rejected before encoding
+        let params = Params {
+            rate_bits: 0,
+            ..params
+        };
+        let poly = DenseMultilinearExtension::<Fr>::rand(0, &mut rng);
+        assert!(matches!(
+            Basefold::<Projective>::commit(&params, &poly),
+            Err(Error::InvalidRate(0, 32))
+        ));
+        let mut ts_prove = PoseidonTranscript::<Projective>::new(&config);
+        assert!(matches!(
+            Basefold::open(&params, &poly, &Fr::zero(), &[], &mut ts_prove),
+            Err(Error::InvalidRate(0, 32))
+        ));
+        let mut ts_verify = PoseidonTranscript::<Projective>::new(&config);
+        assert!(matches!(
+            Basefold::verify(
+                &params,
+                &Fr::zero(),
+                &[],
+                &Fr::zero(),
+                &proof,
+                &mut ts_verify
+            ),
+            Err(Error::InvalidRate(0, 32))
+        ));
+    }
+
+    #[test]
+    fn test_basefold_batch_open() {
+        let mut rng = ark_std::test_rng();
+        let params = Params::new(poseidon_test_config::<Fr>(), 1, 20);
+        let config = poseidon_test_config::<Fr>();
+        let num_vars = 4;
+
+        let polys: Vec<_> = (0..3)
+            .map(|_| DenseMultilinearExtension::<Fr>::rand(num_vars, &mut rng))
+            .collect();
+        let cms: Vec<Fr> = polys
+            .iter()
+            .map(|p| Basefold::<Projective>::commit(&params, p).unwrap())
+            .collect();
+        let point: Vec<Fr> = (0..num_vars).map(|_| Fr::rand(&mut rng)).collect();
+
+        let mut ts_prove = PoseidonTranscript::<Projective>::new(&config);
+        let (proof, values) =
+            Basefold::batch_open(&params, &polys, &cms, &point, &mut ts_prove).unwrap();
+        let mut ts_verify = PoseidonTranscript::<Projective>::new(&config);
+        assert!(
+            Basefold::batch_verify(&params, &cms, &point, &values, &proof, &mut ts_verify).unwrap()
+        );
+
+        let mut wrong = values.clone();
+        wrong[2] += Fr::one();
+        let mut ts_verify = PoseidonTranscript::<Projective>::new(&config);
+        assert!(
+            !Basefold::batch_verify(&params, &cms, &point, &wrong, &proof, &mut ts_verify).unwrap()
+        );
+    }
+}
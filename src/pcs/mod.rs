@@ -0,0 +1,84 @@
+use ark_ec::CurveGroup;
+use ark_poly::DenseMultilinearExtension;
+use ark_serialize::SerializationError;
+use ark_std::fmt::Debug;
+use thiserror::Error;
+
+use crate::transcript::Transcript;
+
+pub mod basefold;
+pub mod pst;
+pub mod spark;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Polynomial has {0} variables, params support at most {1}")]
+    TooManyVariables(usize, usize),
+    #[error("Point has {0} coordinates, expected {1}")]
+    InvalidPoint(usize, usize),
+    #[error("Batch length mismatch: {0} != {1}")]
+    BatchLengthMismatch(usize, usize),
+    #[error("Powers of tau too short: need {0} elements, got {1}")]
+    PowersOfTauTooShort(usize, usize),
+    #[error("Rate of {0} bits must be between 1 and the two-adicity {1} of the field")]
+    InvalidRate(usize, u32),
+    #[error(transparent)]
+    Serialization(#[from] SerializationError),
+    #[error(transparent)]
+    SumCheck(#[from] crate::utils::sum_check::Error),
+    #[error(transparent)]
+    GrandProduct(#[from] crate::utils::grand_product::Error),
+}
+
+/// Commitment scheme for multilinear polynomials given by their evaluations
+/// over the boolean hypercube, opened at points of F^num_vars.
+pub trait MultilinearPCS<C: CurveGroup> {
+    type ProverParams;
+    type VerifierParams;
+    type Commitment: Clone + Debug;
+    type Proof: Clone + Debug;
+    type BatchProof: Clone + Debug;
+
+    fn absorb_commitment(transcript: &mut impl Transcript<C>, cm: &Self::Commitment);
+
+    fn commit(
+        pp: &Self::ProverParams,
+        poly: &DenseMultilinearExtension<C::ScalarField>,
+    ) -> Result<Self::Commitment, Error>;
+
+    /// returns the proof together with poly(point), cm is the commitment to poly
+    fn open(
+        pp: &Self::ProverParams,
+        poly: &DenseMultilinearExtension<C::ScalarField>,
+        cm: &Self::Commitment,
+        point: &[C::ScalarField],
+        transcript: &mut impl Transcript<C>,
+    ) -> Result<(Self::Proof, C::ScalarField), Error>;
+
+    fn verify(
+        vp: &Self::VerifierParams,
+        cm: &Self::Commitment,
+        point: &[C::ScalarField],
+        value: &C::ScalarField,
+        proof: &Self::Proof,
+        transcript: &mut impl Transcript<C>,
+    ) -> Result<bool, Error>;
+
+    /// open several polynomials at the same point, returns the proof and all evaluations
+    fn batch_open(
+        pp: &Self::ProverParams,
+        polys: &[DenseMultilinearExtension<C::ScalarField>],
+        cms: &[Self::Commitment],
+        point: &[C::ScalarField],
+        transcript: &mut impl Transcript<C>,
+    ) -> Result<(Self::BatchProof, Vec<C::ScalarField>), Error>;
+
+    fn batch_verify(
+        vp: &Self::VerifierParams,
+        cms: &[Self::Commitment],
+        point: &[C::ScalarField],
+        values: &[C::ScalarField],
+        proof: &Self::BatchProof,
+        transcript: &mut impl Transcript<C>,
+    ) -> Result<bool, Error>;
+}
@@ -0,0 +1,389 @@
+use ark_ec::{
+    pairing::Pairing, scalar_mul::fixed_base::FixedBase, AffineRepr, CurveGroup, VariableBaseMSM,
+};
+use ark_ff::{One, PrimeField, Zero};
+use ark_poly::{DenseMultilinearExtension, MultilinearExtension};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::{rngs::StdRng, Rng, SeedableRng};
+use ark_std::UniformRand;
+use std::io::{Read, Write};
+use std::marker::PhantomData;
+
+use super::{Error, MultilinearPCS};
+use crate::transcript::Transcript;
+
+/// Powers of a secret tau in both source groups, (g^{tau^i})_i and (h^{tau^i})_i.
+/// Files are the arkworks compressed serialization of this struct.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PowersOfTau<E: Pairing> {
+    pub powers_of_g: Vec<E::G1Affine>,
+    pub powers_of_h: Vec<E::G2Affine>,
+}
+
+impl<E: Pairing> PowersOfTau<E> {
+    pub fn generate<R: Rng>(rng: &mut R, num_g: usize, num_h: usize) -> Self {
+        let tau = E::ScalarField::rand(rng);
+        let powers: Vec<E::ScalarField> =
+            std::iter::successors(Some(E::ScalarField::one()), |p| Some(*p * tau))
+                .take(num_g.max(num_h))
+                .collect();
+        let g = fixed_base_mul(E::G1::rand(rng), &powers[..num_g]);
+        let h = fixed_base_mul(E::G2::rand(rng), &powers[..num_h]);
+        Self {
+            powers_of_g: E::G1::normalize_batch(&g),
+            powers_of_h: E::G2::normalize_batch(&h),
+        }
+    }
+
+    pub fn read<R: Read>(reader: R) -> Result<Self, Error> {
+        Ok(Self::deserialize_compressed(reader)?)
+    }
+
+    pub fn write<W: Write>(&self, writer: W) -> Result<(), Error> {
+        Ok(self.serialize_compressed(writer)?)
+    }
+}
+
+fn fixed_base_mul<G: CurveGroup>(g: G, scalars: &[G::ScalarField]) -> Vec<G> {
+    let scalar_size = G::ScalarField::MODULUS_BIT_SIZE as usize;
+    let window = FixedBase::get_mul_window_size(scalars.len());
+    let table = FixedBase::get_window_table(scalar_size, window, g);
+    FixedBase::msm(scalar_size, window, &table, scalars)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ProverParams<E: Pairing> {
+    pub num_vars: usize,
+    // powers_of_g[k][b] = g^{eq(tau_k..tau_{num_vars-1}, b)}, b in {0,1}^{num_vars-k}
+    pub powers_of_g: Vec<Vec<E::G1Affine>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct VerifierParams<E: Pairing> {
+    pub num_vars: usize,
+    pub g: E::G1Affine,
+    pub h: E::G2Affine,
+    // h^{tau_i}
+    pub h_tau: Vec<E::G2Affine>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Proof<E: Pairing> {
+    // commitments to the quotients q_i(x_{i+1}, .., x_{n-1})
+    pub proofs: Vec<E::G1Affine>,
+}
+
+/// Multilinear KZG (Papamanthou-Shi-Tamassia) over a pairing-friendly curve.
+/// f(x) - f(r) = sum_i (x_i - r_i) * q_i(x_{i+1}, ..), checked in the exponent at tau.
+pub struct PST<E: Pairing> {
+    _e: PhantomData<E>,
+}
+
+impl<E: Pairing> PST<E> {
+    pub fn setup<R: Rng>(rng: &mut R, num_vars: usize) -> (ProverParams<E>, VerifierParams<E>) {
+        let ptau = PowersOfTau::generate(rng, 1 << num_vars, num_h_powers(num_vars));
+        Self::setup_from_powers_of_tau(&ptau, num_vars).unwrap()
+    }
+
+    pub fn setup_from_seed(
+        seed: [u8; 32],
+        num_vars: usize,
+    ) -> (ProverParams<E>, VerifierParams<E>) {
+        Self::setup(&mut StdRng::from_seed(seed), num_vars)
+    }
+
+    /// Derives the multilinear SRS from univariate powers by setting tau_i = tau^{2^i}
+    pub fn setup_from_powers_of_tau(
+        ptau: &PowersOfTau<E>,
+        num_vars: usize,
+    ) -> Result<(ProverParams<E>, VerifierParams<E>), Error> {
+        if ptau.powers_of_g.len() < 1 << num_vars {
+            return Err(Error::PowersOfTauTooShort(
+                1 << num_vars,
+                ptau.powers_of_g.len(),
+            ));
+        }
+        if ptau.powers_of_h.len() < num_h_powers(num_vars) {
+            return Err(Error::PowersOfTauTooShort(
+                num_h_powers(num_vars),
+                ptau.powers_of_h.len(),
+            ));
+        }
+
+        let powers_of_g = (0..=num_vars)
+            .map(|k| {
+                // eq(tau_k.., b) = sum_{c ⊇ b} (-1)^{|c \ b|} tau^{c << k}, a superset
+                // mobius transform over the powers of tau
+                let mut evals: Vec<E::G1> = (0..1 << (num_vars - k))
+                    .map(|c| ptau.powers_of_g[c << k].into())
+                    .collect();
+                for j in 0..num_vars - k {
+                    for b in 0..evals.len() {
+                        if b & (1 << j) == 0 {
+                            let hi = evals[b | (1 << j)];
+                            evals[b] -= hi;
+                        }
+                    }
+                }
+                E::G1::normalize_batch(&evals)
+            })
+            .collect();
+        let h_tau = (0..num_vars).map(|i| ptau.powers_of_h[1 << i]).collect();
+
+        let pp = ProverParams {
+            num_vars,
+            powers_of_g,
+        };
+        let vp = VerifierParams {
+            num_vars,
+            g: ptau.powers_of_g[0],
+            h: ptau.powers_of_h[0],
+            h_tau,
+        };
+        Ok((pp, vp))
+    }
+}
+
+fn num_h_powers(num_vars: usize) -> usize {
+    if num_vars == 0 {
+        1
+    } else {
+        (1 << (num_vars - 1)) + 1
+    }
+}
+
+impl<E: Pairing> MultilinearPCS<E::G1> for PST<E> {
+    type ProverParams = ProverParams<E>;
+    type VerifierParams = VerifierParams<E>;
+    type Commitment = E::G1;
+    type Proof = Proof<E>;
+    type BatchProof = Proof<E>;
+
+    fn absorb_commitment(transcript: &mut impl Transcript<E::G1>, cm: &E::G1) {
+        transcript.absorb_point(cm);
+    }
+
+    fn commit(
+        pp: &ProverParams<E>,
+        poly: &DenseMultilinearExtension<E::ScalarField>,
+    ) -> Result<E::G1, Error> {
+        if poly.num_vars > pp.num_vars {
+            return Err(Error::TooManyVariables(poly.num_vars, pp.num_vars));
+        }
+        // polynomials with fewer variables use the last num_vars of tau
+        let offset = pp.num_vars - poly.num_vars;
+        Ok(E::G1::msm_unchecked(
+            &pp.powers_of_g[offset],
+            &poly.evaluations,
+        ))
+    }
+
+    fn open(
+        pp: &ProverParams<E>,
+        poly: &DenseMultilinearExtension<E::ScalarField>,
+        _cm: &E::G1,
+        point: &[E::ScalarField],
+        _transcript: &mut impl Transcript<E::G1>,
+    ) -> Result<(Proof<E>, E::ScalarField), Error> {
+        if poly.num_vars > pp.num_vars {
+            return Err(Error::TooManyVariables(poly.num_vars, pp.num_vars));
+        }
+        if point.len() != poly.num_vars {
+            return Err(Error::InvalidPoint(point.len(), poly.num_vars));
+        }
+        let offset = pp.num_vars - poly.num_vars;
+
+        let mut f = poly.evaluations.clone();
+        let mut proofs = Vec::with_capacity(point.len());
+        for (i, r_i) in point.iter().enumerate() {
+            let half = f.len() / 2;
+            // q_i = f(.., 1, ..) - f(.., 0, ..), then fix x_i = r_i
+            let q: Vec<E::ScalarField> = (0..half).map(|b| f[2 * b + 1] - f[2 * b]).collect();
+            f = (0..half).map(|b| f[2 * b] + *r_i * q[b]).collect();
+            proofs.push(E::G1::msm_unchecked(&pp.powers_of_g[offset + i + 1], &q));
+        }
+        let proof = Proof {
+            proofs: E::G1::normalize_batch(&proofs),
+        };
+        Ok((proof, f[0]))
+    }
+
+    fn verify(
+        vp: &VerifierParams<E>,
+        cm: &E::G1,
+        point: &[E::ScalarField],
+        value: &E::ScalarField,
+        proof: &Proof<E>,
+        _transcript: &mut impl Transcript<E::G1>,
+    ) -> Result<bool, Error> {
+        if point.len() > vp.num_vars {
+            return Err(Error::TooManyVariables(point.len(), vp.num_vars));
+        }
+        if proof.proofs.len() != point.len() {
+            return Err(Error::InvalidPoint(point.len(), proof.proofs.len()));
+        }
+        let offset = vp.num_vars - point.len();
+
+        // e(cm - g^v, h) == prod_i e(pi_i, h^{tau_i - r_i})
+        let h: E::G2 = vp.h.into();
+        let g1 = std::iter::once(*cm - vp.g * value)
+            .chain(proof.proofs.iter().map(|p| -p.into_group()))
+            .collect::<Vec<_>>();
+        let g2 = std::iter::once(h)
+            .chain(
+                point
+                    .iter()
+                    .enumerate()
+                    .map(|(i, r_i)| vp.h_tau[offset + i].into_group() - h * r_i),
+            )
+            .collect::<Vec<_>>();
+        Ok(E::multi_pairing(g1, g2).is_zero())
+    }
+
+    fn batch_open(
+        pp: &ProverParams<E>,
+        polys: &[DenseMultilinearExtension<E::ScalarField>],
+        cms: &[E::G1],
+        point: &[E::ScalarField],
+        transcript: &mut impl Transcript<E::G1>,
+    ) -> Result<(Proof<E>, Vec<E::ScalarField>), Error> {
+        if polys.len() != cms.len() {
+            return Err(Error::BatchLengthMismatch(polys.len(), cms.len()));
+        }
+        let mut values = Vec::with_capacity(polys.len());
+        for poly in polys {
+            values.push(
+                poly.evaluate(point)
+                    .ok_or(Error::InvalidPoint(point.len(), poly.num_vars))?,
+            );
+        }
+        let gamma = Self::batch_challenge(cms, &values, transcript);
+
+        // open sum_i gamma^i * f_i
+        let mut combined = vec![E::ScalarField::zero(); 1 << point.len()];
+        let mut cm = E::G1::zero();
+        let mut gamma_i = E::ScalarField::one();
+        for (poly, cm_i) in polys.iter().zip(cms) {
+            for (c, f) in combined.iter_mut().zip(&poly.evaluations) {
+                *c += gamma_i * f;
+            }
+            cm += *cm_i * gamma_i;
+            gamma_i *= gamma;
+        }
+        let combined = DenseMultilinearExtension::from_evaluations_vec(point.len(), combined);
+        let (proof, _) = Self::open(pp, &combined, &cm, point, transcript)?;
+        Ok((proof, values))
+    }
+
+    fn batch_verify(
+        vp: &VerifierParams<E>,
+        cms: &[E::G1],
+        point: &[E::ScalarField],
+        values: &[E::ScalarField],
+        proof: &Proof<E>,
+        transcript: &mut impl Transcript<E::G1>,
+    ) -> Result<bool, Error> {
+        if values.len() != cms.len() {
+            return Err(Error::BatchLengthMismatch(values.len(), cms.len()));
+        }
+        let gamma = Self::batch_challenge(cms, values, transcript);
+
+        let mut cm = E::G1::zero();
+        let mut value = E::ScalarField::zero();
+        let mut gamma_i = E::ScalarField::one();
+        for (cm_i, v_i) in cms.iter().zip(values) {
+            cm += *cm_i * gamma_i;
+            value += gamma_i * v_i;
+            gamma_i *= gamma;
+        }
+        Self::verify(vp, &cm, point, &value, proof, transcript)
+    }
+}
+
+impl<E: Pairing> PST<E> {
+    fn batch_challenge(
+        cms: &[E::G1],
+        values: &[E::ScalarField],
+        transcript: &mut impl Transcript<E::G1>,
+    ) -> E::ScalarField {
+        for cm in cms {
+            Self::absorb_commitment(transcript, cm);
+        }
+        transcript.absorb_vec(values);
+        transcript.get_challenge()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bn254::{Bn254, Fr, G1Projective};
+
+    use super::*;
+    use crate::transcript::poseidon::{tests::poseidon_test_config, PoseidonTranscript};
+
+    #[test]
+    fn test_pst_open() {
+        let mut rng = ark_std::test_rng();
+        let num_vars = 5;
+        let (pp, vp) = PST::<Bn254>::setup(&mut rng, num_vars);
+        let config = poseidon_test_config::<Fr>();
+        let mut ts = PoseidonTranscript::<G1Projective>::new(&config);
+
+        // also cover polynomials smaller than the params
+        for nv in [num_vars, 3, 0] {
+            let poly = DenseMultilinearExtension::<Fr>::rand(nv, &mut rng);
+            let point: Vec<Fr> = (0..nv).map(|_| Fr::rand(&mut rng)).collect();
+            let cm = PST::commit(&pp, &poly).unwrap();
+            let (proof, value) = PST::open(&pp, &poly, &cm, &point, &mut ts).unwrap();
+            assert_eq!(value, poly.evaluate(&point).unwrap());
+            assert!(PST::verify(&vp, &cm, &point, &value, &proof, &mut ts).unwrap());
+            let wrong = value + Fr::one();
+            assert!(!PST::verify(&vp, &cm, &point, &wrong, &proof, &mut ts).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_pst_batch_open() {
+        let mut rng = ark_std::test_rng();
+        let num_vars = 4;
+        let (pp, vp) = PST::<Bn254>::setup_from_seed([7u8; 32], num_vars);
+        let config = poseidon_test_config::<Fr>();
+        let mut ts_prove = PoseidonTranscript::<G1Projective>::new(&config);
+        let mut ts_verify = PoseidonTranscript::<G1Projective>::new(&config);
+
+        let polys: Vec<_> = (0..3)
+            .map(|_| DenseMultilinearExtension::<Fr>::rand(num_vars, &mut rng))
+            .collect();
+        let cms: Vec<_> = polys.iter().map(|p| PST::commit(&pp, p).unwrap()).collect();
+        let point: Vec<Fr> = (0..num_vars).map(|_| Fr::rand(&mut rng)).collect();
+        let (proof, values) = PST::batch_open(&pp, &polys, &cms, &point, &mut ts_prove).unwrap();
+        assert!(PST::batch_verify(&vp, &cms, &point, &values, &proof, &mut ts_verify).unwrap());
+
+        let mut ts_verify = PoseidonTranscript::<G1Projective>::new(&config);
+        let mut wrong = values.clone();
+        wrong[1] += Fr::one();
+        assert!(!PST::batch_verify(&vp, &cms, &point, &wrong, &proof, &mut ts_verify).unwrap());
+    }
+
+    #[test]
+    fn test_pst_powers_of_tau_file() {
+        let mut rng = ark_std::test_rng();
+        let num_vars = 3;
+        let ptau = PowersOfTau::<Bn254>::generate(&mut rng, 1 << num_vars, 1 << num_vars);
+        let mut bytes = Vec::new();
+        ptau.write(&mut bytes).unwrap();
+        let ptau_read = PowersOfTau::<Bn254>::read(&bytes[..]).unwrap();
+        assert_eq!(ptau, ptau_read);
+
+        let (pp, vp) = PST::setup_from_powers_of_tau(&ptau_read, num_vars).unwrap();
+        assert!(PST::<Bn254>::setup_from_powers_of_tau(&ptau_read, num_vars + 1).is_err());
+
+        let config = poseidon_test_config::<Fr>();
+        let mut ts = PoseidonTranscript::<G1Projective>::new(&config);
+        let poly = DenseMultilinearExtension::<Fr>::rand(num_vars, &mut rng);
+        let point: Vec<Fr> = (0..num_vars).map(|_| Fr::rand(&mut rng)).collect();
+        let cm = PST::commit(&pp, &poly).unwrap();
+        let (proof, value) = PST::open(&pp, &poly, &cm, &point, &mut ts).unwrap();
+        assert!(PST::verify(&vp, &cm, &point, &value, &proof, &mut ts).unwrap());
+    }
+}
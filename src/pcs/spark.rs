@@ -0,0 +1,594 @@
+use ark_ec::CurveGroup;
+use ark_ff::PrimeField;
+use ark_std::{log2, One, Zero};
+use std::marker::PhantomData;
+
+use super::{Error, MultilinearPCS};
+use crate::ccs::r1cs::{validate_matrix, SparseMatrix};
+use crate::ccs::Error as CCSError;
+use crate::transcript::Transcript;
+use crate::utils::grand_product::{GrandProduct, GrandProductProof};
+use crate::utils::mle::{eq_eval, eq_evals, identity_eval, vec_to_mle};
+use crate::utils::sum_check::{SumCheck, SumCheckProof, VirtualPolynomial};
+
+/// Dense form of a sparse matrix, the k-th entry is (row[k], col[k], val[k]),
+/// padded with zero entries to a power of two, together with the timestamps
+/// of the offline memory checking over the row and column address spaces.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SparkPolys<F: PrimeField> {
+    // log of the padded number of entries
+    pub num_vars: usize,
+    // s = log(n_rows), s_prime = log(n_cols)
+    pub s: usize,
+    pub s_prime: usize,
+
+    pub row: Vec<F>,
+    pub col: Vec<F>,
+    pub val: Vec<F>,
+    pub read_ts_row: Vec<F>,
+    pub read_ts_col: Vec<F>,
+    pub final_ts_row: Vec<F>,
+    pub final_ts_col: Vec<F>,
+}
+
+impl<F: PrimeField> SparkPolys<F> {
+    /// fails if an entry of m is out of range, as its fields are public
+    pub fn new(m: &SparseMatrix<F>) -> Result<Self, CCSError<F>> {
+        validate_matrix(0, m, m.n_rows, m.n_cols)?;
+        let num_vars = log2(m.vals.len().max(1)) as usize;
+        let s = log2(m.n_rows) as usize;
+        let s_prime = log2(m.n_cols) as usize;
+
+        // padding entries read address 0 of both memories
+        let entries = m
+            .vals
+            .iter()
+            .map(|(i, j, v)| (*i, *j, *v))
+            .chain(std::iter::repeat((0, 0, F::zero())))
+            .take(1 << num_vars);
+
+        let mut counts_row = vec![0u64; 1 << s];
+        let mut counts_col = vec![0u64; 1 << s_prime];
+        let mut polys = Self {
+            num_vars,
+            s,
+            s_prime,
+            row: Vec::with_capacity(1 << num_vars),
+            col: Vec::with_capacity(1 << num_vars),
+            val: Vec::with_capacity(1 << num_vars),
+            read_ts_row: Vec::with_capacity(1 << num_vars),
+            read_ts_col: Vec::with_capacity(1 << num_vars),
+            final_ts_row: Vec::new(),
+            final_ts_col: Vec::new(),
+        };
+        for (i, j, v) in entries {
+            polys.row.push(F::from(i as u64));
+            polys.col.push(F::from(j as u64));
+            polys.val.push(v);
+            polys.read_ts_row.push(F::from(counts_row[i]));
+            polys.read_ts_col.push(F::from(counts_col[j]));
+            counts_row[i] += 1;
+            counts_col[j] += 1;
+        }
+        polys.final_ts_row = counts_row.into_iter().map(F::from).collect();
+        polys.final_ts_col = counts_col.into_iter().map(F::from).collect();
+        Ok(polys)
+    }
+}
+
+pub struct SparkCommitment<C: CurveGroup, P: MultilinearPCS<C>> {
+    pub num_vars: usize,
+    pub s: usize,
+    pub s_prime: usize,
+
+    pub row: P::Commitment,
+    pub col: P::Commitment,
+    pub val: P::Commitment,
+    pub read_ts_row: P::Commitment,
+    pub read_ts_col: P::Commitment,
+    pub final_ts_row: P::Commitment,
+    pub final_ts_col: P::Commitment,
+}
+
+impl<C: CurveGroup, P: MultilinearPCS<C>> SparkCommitment<C, P> {
+    fn absorb(&self, transcript: &mut impl Transcript<C>) {
+        for cm in [
+            &self.row,
+            &self.col,
+            &self.val,
+            &self.read_ts_row,
+            &self.read_ts_col,
+            &self.final_ts_row,
+            &self.final_ts_col,
+        ] {
+            P::absorb_commitment(transcript, cm);
+        }
+    }
+}
+
+pub struct SparkProof<C: CurveGroup, P: MultilinearPCS<C>> {
+    // e_rx[k] = eq(r_x, row[k]), e_ry[k] = eq(r_y, col[k])
+    pub cm_e_rx: P::Commitment,
+    pub cm_e_ry: P::Commitment,
+
+    // sum_k val[k] * e_rx[k] * e_ry[k]
+    pub sum_check: SumCheckProof<C::ScalarField>,
+    // val, e_rx, e_ry at the sum-check point
+    pub eval_values: Vec<C::ScalarField>,
+    pub eval_proof: P::BatchProof,
+
+    // row read, row write, col read, col write
+    pub ops_products: Vec<C::ScalarField>,
+    pub ops_proof: GrandProductProof<C::ScalarField>,
+    // row, col, e_rx, e_ry, read_ts_row, read_ts_col at the ops point
+    pub ops_values: Vec<C::ScalarField>,
+    pub ops_opening: P::BatchProof,
+
+    // init and audit of the row memory
+    pub row_mem_products: Vec<C::ScalarField>,
+    pub row_mem_proof: GrandProductProof<C::ScalarField>,
+    pub final_ts_row_value: C::ScalarField,
+    pub final_ts_row_opening: P::Proof,
+
+    // init and audit of the col memory
+    pub col_mem_products: Vec<C::ScalarField>,
+    pub col_mem_proof: GrandProductProof<C::ScalarField>,
+    pub final_ts_col_value: C::ScalarField,
+    pub final_ts_col_opening: P::Proof,
+}
+
+/// Spark sparse matrix commitment: proves M~(r_x, r_y) = sum_k val[k] * e_rx[k] * e_ry[k]
+/// and that e_rx, e_ry are honest reads of the memories eq(r_x, .), eq(r_y, .) with
+/// offline memory checking, so the verifier work is polylogarithmic in the non-zeros.
+pub struct Spark<C: CurveGroup, P: MultilinearPCS<C>> {
+    _c: PhantomData<C>,
+    _p: PhantomData<P>,
+}
+
+impl<C: CurveGroup, P: MultilinearPCS<C>> Spark<C, P> {
+    pub fn commit(
+        pp: &P::ProverParams,
+        polys: &SparkPolys<C::ScalarField>,
+    ) -> Result<SparkCommitment<C, P>, Error> {
+        let commit =
+            |v: &[C::ScalarField], num_vars: usize| P::commit(pp, &vec_to_mle(num_vars, v));
+        Ok(SparkCommitment {
+            num_vars: polys.num_vars,
+            s: polys.s,
+            s_prime: polys.s_prime,
+            row: commit(&polys.row, polys.num_vars)?,
+            col: commit(&polys.col, polys.num_vars)?,
+            val: commit(&polys.val, polys.num_vars)?,
+            read_ts_row: commit(&polys.read_ts_row, polys.num_vars)?,
+            read_ts_col: commit(&polys.read_ts_col, polys.num_vars)?,
+            final_ts_row: commit(&polys.final_ts_row, polys.s)?,
+            final_ts_col: commit(&polys.final_ts_col, polys.s_prime)?,
+        })
+    }
+
+    pub fn prove_eval(
+        pp: &P::ProverParams,
+        polys: &SparkPolys<C::ScalarField>,
+        cm: &SparkCommitment<C, P>,
+        r_x: &[C::ScalarField],
+        r_y: &[C::ScalarField],
+        transcript: &mut impl Transcript<C>,
+    ) -> Result<(SparkProof<C, P>, C::ScalarField), Error> {
+        if r_x.len() != polys.s {
+            return Err(Error::InvalidPoint(r_x.len(), polys.s));
+        }
+        if r_y.len() != polys.s_prime {
+            return Err(Error::InvalidPoint(r_y.len(), polys.s_prime));
+        }
+        let nv = polys.num_vars;
+        cm.absorb(transcript);
+        transcript.absorb_vec(r_x);
+        transcript.absorb_vec(r_y);
+
+        let mem_rx = eq_evals(r_x);
+        let mem_ry = eq_evals(r_y);
+        let e_rx: Vec<C::ScalarField> = polys.row.iter().map(|i| mem_rx[to_usize(i)]).collect();
+        let e_ry: Vec<C::ScalarField> = polys.col.iter().map(|j| mem_ry[to_usize(j)]).collect();
+        let cm_e_rx = P::commit(pp, &vec_to_mle(nv, &e_rx))?;
+        let cm_e_ry = P::commit(pp, &vec_to_mle(nv, &e_ry))?;
+        P::absorb_commitment(transcript, &cm_e_rx);
+        P::absorb_commitment(transcript, &cm_e_ry);
+
+        let value: C::ScalarField = (0..1 << nv).map(|k| polys.val[k] * e_rx[k] * e_ry[k]).sum();
+        transcript.absorb(&value);
+        let mut poly = VirtualPolynomial::new(nv);
+        let val = poly.add_mle(vec_to_mle(nv, &polys.val));
+        let rx = poly.add_mle(vec_to_mle(nv, &e_rx));
+        let ry = poly.add_mle(vec_to_mle(nv, &e_ry));
+        poly.add_product(C::ScalarField::one(), vec![val, rx, ry]);
+        let (sum_check, r_k) = SumCheck::prove(&poly, transcript);
+        let (eval_proof, eval_values) = P::batch_open(
+            pp,
+            &poly.mles,
+            &[cm.val.clone(), cm_e_rx.clone(), cm_e_ry.clone()],
+            &r_k,
+            transcript,
+        )?;
+
+        let gamma = transcript.get_challenge();
+        let tau = transcript.get_challenge();
+        let hash = |a: &C::ScalarField, v: &C::ScalarField, t: C::ScalarField| {
+            *a * gamma * gamma + *v * gamma + t - tau
+        };
+        let one = C::ScalarField::one();
+
+        let ops_leaves = vec![
+            leaves(
+                &polys.row,
+                &e_rx,
+                &polys.read_ts_row,
+                C::ScalarField::zero(),
+                hash,
+            ),
+            leaves(&polys.row, &e_rx, &polys.read_ts_row, one, hash),
+            leaves(
+                &polys.col,
+                &e_ry,
+                &polys.read_ts_col,
+                C::ScalarField::zero(),
+                hash,
+            ),
+            leaves(&polys.col, &e_ry, &polys.read_ts_col, one, hash),
+        ];
+        let (ops_products, ops_proof, r_ops) = GrandProduct::prove(&ops_leaves, transcript);
+        let (ops_opening, ops_values) = P::batch_open(
+            pp,
+            &[
+                vec_to_mle(nv, &polys.row),
+                vec_to_mle(nv, &polys.col),
+                vec_to_mle(nv, &e_rx),
+                vec_to_mle(nv, &e_ry),
+                vec_to_mle(nv, &polys.read_ts_row),
+                vec_to_mle(nv, &polys.read_ts_col),
+            ],
+            &[
+                cm.row.clone(),
+                cm.col.clone(),
+                cm_e_rx.clone(),
+                cm_e_ry.clone(),
+                cm.read_ts_row.clone(),
+                cm.read_ts_col.clone(),
+            ],
+            &r_ops,
+            transcript,
+        )?;
+
+        let (row_mem_products, row_mem_proof, final_ts_row_value, final_ts_row_opening) =
+            Self::prove_memory(
+                pp,
+                &mem_rx,
+                &polys.final_ts_row,
+                &cm.final_ts_row,
+                polys.s,
+                hash,
+                transcript,
+            )?;
+        let (col_mem_products, col_mem_proof, final_ts_col_value, final_ts_col_opening) =
+            Self::prove_memory(
+                pp,
+                &mem_ry,
+                &polys.final_ts_col,
+                &cm.final_ts_col,
+                polys.s_prime,
+                hash,
+                transcript,
+            )?;
+
+        let proof = SparkProof {
+            cm_e_rx,
+            cm_e_ry,
+            sum_check,
+            eval_values,
+            eval_proof,
+            ops_products,
+            ops_proof,
+            ops_values,
+            ops_opening,
+            row_mem_products,
+            row_mem_proof,
+            final_ts_row_value,
+            final_ts_row_opening,
+            col_mem_products,
+            col_mem_proof,
+            final_ts_col_value,
+            final_ts_col_opening,
+        };
+        Ok((proof, value))
+    }
+
+    #[allow(clippy::type_complexity, clippy::too_many_arguments)]
+    fn prove_memory(
+        pp: &P::ProverParams,
+        mem: &[C::ScalarField],
+        final_ts: &[C::ScalarField],
+        cm_final_ts: &P::Commitment,
+        num_vars: usize,
+        hash: impl Fn(&C::ScalarField, &C::ScalarField, C::ScalarField) -> C::ScalarField,
+        transcript: &mut impl Transcript<C>,
+    ) -> Result<
+        (
+            Vec<C::ScalarField>,
+            GrandProductProof<C::ScalarField>,
+            C::ScalarField,
+            P::Proof,
+        ),
+        Error,
+    > {
+        let addr: Vec<C::ScalarField> = (0..mem.len() as u64).map(C::ScalarField::from).collect();
+        let zeros = vec![C::ScalarField::zero(); mem.len()];
+        let mem_leaves = vec![
+            leaves(&addr, mem, &zeros, C::ScalarField::zero(), &hash),
+            leaves(&addr, mem, final_ts, C::ScalarField::zero(), &hash),
+        ];
+        let (products, proof, r_mem) = GrandProduct::prove(&mem_leaves, transcript);
+        let (opening, value) = P::open(
+            pp,
+            &vec_to_mle(num_vars, final_ts),
+            cm_final_ts,
+            &r_mem,
+            transcript,
+        )?;
+        Ok((products, proof, value, opening))
+    }
+
+    pub fn verify_eval(
+        vp: &P::VerifierParams,
+        cm: &SparkCommitment<C, P>,
+        r_x: &[C::ScalarField],
+        r_y: &[C::ScalarField],
+        value: &C::ScalarField,
+        proof: &SparkProof<C, P>,
+        transcript: &mut impl Transcript<C>,
+    ) -> Result<bool, Error> {
+        if r_x.len() != cm.s {
+            return Err(Error::InvalidPoint(r_x.len(), cm.s));
+        }
+        if r_y.len() != cm.s_prime {
+            return Err(Error::InvalidPoint(r_y.len(), cm.s_prime));
+        }
+        if proof.eval_values.len() != 3 {
+            return Err(Error::BatchLengthMismatch(proof.eval_values.len(), 3));
+        }
+        if proof.ops_values.len() != 6 {
+            return Err(Error::BatchLengthMismatch(proof.ops_values.len(), 6));
+        }
+        cm.absorb(transcript);
+        transcript.absorb_vec(r_x);
+        transcript.absorb_vec(r_y);
+        P::absorb_commitment(transcript, &proof.cm_e_rx);
+        P::absorb_commitment(transcript, &proof.cm_e_ry);
+
+        transcript.absorb(value);
+        let (expected, r_k) =
+            SumCheck::verify(*value, &proof.sum_check, cm.num_vars, 3, transcript)?;
+        if !P::batch_verify(
+            vp,
+            &[cm.val.clone(), proof.cm_e_rx.clone(), proof.cm_e_ry.clone()],
+            &r_k,
+            &proof.eval_values,
+            &proof.eval_proof,
+            transcript,
+        )? {
+            return Ok(false);
+        }
+        if expected != proof.eval_values.iter().product::<C::ScalarField>() {
+            return Ok(false);
+        }
+
+        let gamma = transcript.get_challenge();
+        let tau = transcript.get_challenge();
+        let hash = |a: &C::ScalarField, v: &C::ScalarField, t: C::ScalarField| {
+            *a * gamma * gamma + *v * gamma + t - tau
+        };
+        let one = C::ScalarField::one();
+
+        let (claims, r_ops) = GrandProduct::verify(
+            &proof.ops_products,
+            &proof.ops_proof,
+            cm.num_vars,
+            transcript,
+        )?;
+        if !P::batch_verify(
+            vp,
+            &[
+                cm.row.clone(),
+                cm.col.clone(),
+                proof.cm_e_rx.clone(),
+                proof.cm_e_ry.clone(),
+                cm.read_ts_row.clone(),
+                cm.read_ts_col.clone(),
+            ],
+            &r_ops,
+            &proof.ops_values,
+            &proof.ops_opening,
+            transcript,
+        )? {
+            return Ok(false);
+        }
+        let [row, col, e_rx, e_ry, ts_row, ts_col] = proof.ops_values[..] else {
+            unreachable!()
+        };
+        if claims
+            != [
+                hash(&row, &e_rx, ts_row),
+                hash(&row, &e_rx, ts_row + one),
+                hash(&col, &e_ry, ts_col),
+                hash(&col, &e_ry, ts_col + one),
+            ]
+        {
+            return Ok(false);
+        }
+
+        for (r, num_vars, products, mem_proof, final_ts_value, final_ts_opening, cm_final_ts) in [
+            (
+                r_x,
+                cm.s,
+                &proof.row_mem_products,
+                &proof.row_mem_proof,
+                &proof.final_ts_row_value,
+                &proof.final_ts_row_opening,
+                &cm.final_ts_row,
+            ),
+            (
+                r_y,
+                cm.s_prime,
+                &proof.col_mem_products,
+                &proof.col_mem_proof,
+                &proof.final_ts_col_value,
+                &proof.final_ts_col_opening,
+                &cm.final_ts_col,
+            ),
+        ] {
+            let (claims, r_mem) = GrandProduct::verify(products, mem_proof, num_vars, transcript)?;
+            if !P::verify(
+                vp,
+                cm_final_ts,
+                &r_mem,
+                final_ts_value,
+                final_ts_opening,
+                transcript,
+            )? {
+                return Ok(false);
+            }
+            // init and audit leaves are computed by the verifier in O(log) time
+            let addr = identity_eval(&r_mem);
+            let mem = eq_eval(r, &r_mem);
+            if claims
+                != [
+                    hash(&addr, &mem, C::ScalarField::zero()),
+                    hash(&addr, &mem, *final_ts_value),
+                ]
+            {
+                return Ok(false);
+            }
+        }
+
+        // init * write == read * audit, for rows and cols
+        let [row_read, row_write, col_read, col_write] = proof.ops_products[..] else {
+            return Err(Error::BatchLengthMismatch(proof.ops_products.len(), 4));
+        };
+        Ok(
+            proof.row_mem_products[0] * row_write == row_read * proof.row_mem_products[1]
+                && proof.col_mem_products[0] * col_write == col_read * proof.col_mem_products[1],
+        )
+    }
+}
+
+fn leaves<F: PrimeField>(
+    addr: &[F],
+    val: &[F],
+    ts: &[F],
+    ts_offset: F,
+    hash: impl Fn(&F, &F, F) -> F,
+) -> Vec<F> {
+    addr.iter()
+        .zip(val)
+        .zip(ts)
+        .map(|((a, v), t)| hash(a, v, *t + ts_offset))
+        .collect()
+}
+
+fn to_usize<F: PrimeField>(f: &F) -> usize {
+    f.into_bigint().as_ref()[0] as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bn254::{Bn254, Fr, G1Projective};
+    use ark_std::{rand::Rng, UniformRand};
+
+    use super::*;
+    use crate::ccs::r1cs::tests::get_test_r1cs;
+    use crate::pcs::basefold::{Basefold, Params};
+    use crate::pcs::pst::PST;
+    use crate::transcript::poseidon::{tests::poseidon_test_config, PoseidonTranscript};
+
+    fn check_spark(m: &SparseMatrix<Fr>) {
+        let mut rng = ark_std::test_rng();
+        let polys = SparkPolys::new(m).unwrap();
+        let max_vars = polys.num_vars.max(polys.s).max(polys.s_prime);
+        let (pp, vp) = PST::<Bn254>::setup(&mut rng, max_vars);
+        check_spark_with::<PST<Bn254>>(m, &polys, &pp, &vp);
+    }
+
+    fn check_spark_with<P: MultilinearPCS<G1Projective>>(
+        m: &SparseMatrix<Fr>,
+        polys: &SparkPolys<Fr>,
+        pp: &P::ProverParams,
+        vp: &P::VerifierParams,
+    ) {
+        let mut rng = ark_std::test_rng();
+        let cm = Spark::<G1Projective, P>::commit(pp, polys).unwrap();
+
+        let r_x: Vec<Fr> = (0..polys.s).map(|_| Fr::rand(&mut rng)).collect();
+        let r_y: Vec<Fr> = (0..polys.s_prime).map(|_| Fr::rand(&mut rng)).collect();
+        let config = poseidon_test_config::<Fr>();
+        let mut ts_prove = PoseidonTranscript::<G1Projective>::new(&config);
+        let (proof, value) = Spark::prove_eval(pp, polys, &cm, &r_x, &r_y, &mut ts_prove).unwrap();
+        assert_eq!(value, m.evaluate_mle(&r_x, &r_y));
+
+        let mut ts_verify = PoseidonTranscript::<G1Projective>::new(&config);
+        assert!(Spark::verify_eval(vp, &cm, &r_x, &r_y, &value, &proof, &mut ts_verify).unwrap());
+
+        let mut ts_verify = PoseidonTranscript::<G1Projective>::new(&config);
+        let wrong = value + Fr::one();
+        assert!(
+            !Spark::verify_eval(vp, &cm, &r_x, &r_y, &wrong, &proof, &mut ts_verify)
+                .unwrap_or(false)
+        );
+    }
+
+    #[test]
+    fn test_spark_r1cs_matrices() {
+        let r1cs = get_test_r1cs::<Fr>();
+        for m in [&r1cs.a, &r1cs.b, &r1cs.c] {
+            check_spark(m);
+        }
+    }
+
+    #[test]
+    fn test_spark_random_matrix() {
+        let mut rng = ark_std::test_rng();
+        let (n_rows, n_cols) = (13, 9);
+        let vals = (0..37)
+            .map(|_| {
+                (
+                    rng.gen_range(0..n_rows),
+                    rng.gen_range(0..n_cols),
+                    Fr::rand(&mut rng),
+                )
+            })
+            .collect();
+        check_spark(&SparseMatrix {
+            n_rows,
+            n_cols,
+            vals,
+        });
+    }
+
+    // the preprocessing does not depend on the PCS, here a hash based one
+    #[test]
+    fn test_spark_basefold() {
+        let params = Params::new(poseidon_test_config::<Fr>(), 2, 20);
+        let r1cs = get_test_r1cs::<Fr>();
+        for m in [&r1cs.a, &r1cs.b, &r1cs.c] {
+            let polys = SparkPolys::new(m).unwrap();
+            check_spark_with::<Basefold<G1Projective>>(m, &polys, &params, &params);
+        }
+    }
+
+    #[test]
+    fn test_spark_rejects_out_of_range_entry() {
+        let mut m = get_test_r1cs::<Fr>().a;
+        m.vals.push((4, 0, Fr::one()));
+        assert!(matches!(
+            SparkPolys::new(&m),
+            Err(CCSError::EntryOutOfRange(0, 4, 0))
+        ));
+    }
+}
@@ -1,4 +1,5 @@
 use ark_ec::CurveGroup;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::rand::Rng;
 use ark_std::UniformRand;
 use std::marker::PhantomData;
@@ -8,13 +9,13 @@ use crate::{
     transcript::Transcript,
 };
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
 pub struct Params<C: CurveGroup> {
     pub h: C,
     pub generators: Vec<C::Affine>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
 pub struct Proof<C: CurveGroup> {
     pub r_commit: C,
     pub u: Vec<C::ScalarField>,
@@ -38,14 +39,14 @@ impl<C: CurveGroup> Pedersen<C> {
         }
     }
 
-    pub fn commit(r: &C::ScalarField, params: &Params<C>, v: &Vec<C::ScalarField>) -> C {
+    pub fn commit(r: &C::ScalarField, params: &Params<C>, v: &[C::ScalarField]) -> C {
         //h*r + <g, v>
         params.h.mul(r) + C::msm(&params.generators[..v.len()], v).unwrap()
     }
 
     pub fn prove(
         cm: &C,
-        v: &Vec<C::ScalarField>,
+        v: &[C::ScalarField],
         r: &C::ScalarField,
         params: &Params<C>,
         transcript: &mut impl Transcript<C>,
@@ -58,7 +59,7 @@ impl<C: CurveGroup> Pedersen<C> {
         transcript.absorb_point(&r_commit);
         let e = transcript.get_challenge();
         // u = d + v*e
-        let u = vec_add_vec(&d, &scalar_mul_vec(e, &v));
+        let u = vec_add_vec(&d, &scalar_mul_vec(e, v));
         //r_u = r1 + e*r
         let r_u = r1 + e * r;
         Proof { r_commit, u, r_u }
@@ -88,6 +89,7 @@ mod tests {
     use crate::transcript::poseidon::tests::poseidon_test_config;
     use crate::transcript::poseidon::PoseidonTranscript;
     use ark_pallas::{Fr, Projective};
+    use ark_serialize::{Compress, Validate};
 
     use super::*;
 
@@ -107,4 +109,32 @@ mod tests {
         let verify = Pedersen::<Projective>::verify(cm, proof, &params, &mut ts_verify);
         assert!(verify);
     }
+
+    #[test]
+    fn test_pedersen_serialization() {
+        let mut rng = ark_std::test_rng();
+        let params = Pedersen::<Projective>::new_params(&mut rng, 4);
+        let poseidon_config = poseidon_test_config::<Fr>();
+        let mut ts_prove = PoseidonTranscript::<Projective>::new(&poseidon_config);
+        let v: Vec<Fr> = (0..4).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let cm = Pedersen::<Projective>::commit(&r, &params, &v);
+        let proof = Pedersen::<Projective>::prove(&cm, &v, &r, &params, &mut ts_prove);
+        for compress in [Compress::Yes, Compress::No] {
+            let mut bytes = Vec::new();
+            params.serialize_with_mode(&mut bytes, compress).unwrap();
+            let read = Params::deserialize_with_mode(&bytes[..], compress, Validate::Yes);
+            assert_eq!(read.unwrap(), params);
+            let mut bytes = Vec::new();
+            proof.serialize_with_mode(&mut bytes, compress).unwrap();
+            let read = Proof::deserialize_with_mode(&bytes[..], compress, Validate::Yes);
+            assert_eq!(read.unwrap(), proof);
+        }
+
+        // h moved off the curve
+        let mut bytes = Vec::new();
+        params.serialize_uncompressed(&mut bytes).unwrap();
+        bytes[0] ^= 1;
+        assert!(Params::<Projective>::deserialize_uncompressed(&bytes[..]).is_err());
+    }
 } /* tests */
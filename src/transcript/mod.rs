@@ -1,18 +1,38 @@
 use ark_std::fmt::Debug;
 
 use ark_ec::CurveGroup;
+use ark_ff::{Field, PrimeField};
 
 pub mod poseidon;
 
+/// Transcript over a prime field alone, enough for sum-check. Challenges in an
+/// extension E of F are squeezed as E::extension_degree() base elements, which
+/// keeps sum-check sound over small fields like Goldilocks.
+pub trait FieldTranscript<F: PrimeField> {
+    fn absorb_base(&mut self, v: &[F]);
+    fn squeeze_base(&mut self, n: usize) -> Vec<F>;
 
-pub trait Transcript<C: CurveGroup> {
+    fn absorb_ext<E: Field<BasePrimeField = F>>(&mut self, v: &[E]) {
+        let base: Vec<F> = v
+            .iter()
+            .flat_map(|e| e.to_base_prime_field_elements())
+            .collect();
+        self.absorb_base(&base);
+    }
+
+    fn challenge_ext<E: Field<BasePrimeField = F>>(&mut self) -> E {
+        let base = self.squeeze_base(E::extension_degree() as usize);
+        E::from_base_prime_field_elems(&base).unwrap()
+    }
+}
+
+pub trait Transcript<C: CurveGroup>: FieldTranscript<C::ScalarField> {
     type TranscriptConfig: Debug;
-   
+
     fn new(config: &Self::TranscriptConfig) -> Self;
     fn absorb(&mut self, v: &C::ScalarField);
     fn absorb_vec(&mut self, v: &[C::ScalarField]);
     fn absorb_point(&mut self, p: &C);
     fn get_challenge(&mut self) -> C::ScalarField;
     fn get_challenges(&mut self, n: usize) -> Vec<C::ScalarField>;
-        
 }
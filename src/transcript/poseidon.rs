@@ -5,13 +5,27 @@ use ark_ec::{CurveGroup, Group};
 use ark_ff::BigInteger;
 use ark_ff::Field;
 use ark_ff::PrimeField;
+use ark_std::Zero;
 
-use super::Transcript;
+use super::{FieldTranscript, Transcript};
 
 pub struct PoseidonTranscript<C: CurveGroup> {
     sponge: PoseidonSponge<C::ScalarField>,
 }
 
+impl<C: CurveGroup> FieldTranscript<C::ScalarField> for PoseidonTranscript<C>
+where
+    <C as Group>::ScalarField: Absorb,
+{
+    fn absorb_base(&mut self, v: &[C::ScalarField]) {
+        self.sponge.absorb(&v);
+    }
+
+    fn squeeze_base(&mut self, n: usize) -> Vec<C::ScalarField> {
+        self.get_challenges(n)
+    }
+}
+
 impl<C: CurveGroup> Transcript<C> for PoseidonTranscript<C>
 where
     <C as Group>::ScalarField: Absorb,
@@ -49,9 +63,38 @@ where
     }
 }
 
+/// Poseidon transcript over a field with no curve, e.g. Goldilocks
+pub struct PoseidonFieldTranscript<F: PrimeField + Absorb> {
+    sponge: PoseidonSponge<F>,
+}
+
+impl<F: PrimeField + Absorb> PoseidonFieldTranscript<F> {
+    pub fn new(poseidon_config: &PoseidonConfig<F>) -> Self {
+        Self {
+            sponge: PoseidonSponge::<F>::new(poseidon_config),
+        }
+    }
+}
+
+impl<F: PrimeField + Absorb> FieldTranscript<F> for PoseidonFieldTranscript<F> {
+    fn absorb_base(&mut self, v: &[F]) {
+        self.sponge.absorb(&v);
+    }
+
+    fn squeeze_base(&mut self, n: usize) -> Vec<F> {
+        let c = self.sponge.squeeze_field_elements(n);
+        self.sponge.absorb(&c);
+        c
+    }
+}
+
 fn prepare_point<C: CurveGroup>(p: &C) -> Vec<C::ScalarField> {
     let p_affine = p.into_affine();
-    let p_xy = &p_affine.xy().unwrap();
+    let p_xy = match p_affine.xy() {
+        Some(xy) => xy,
+        // the point at infinity has no affine coordinates, absorb it as (0, 0)
+        None => return vec![C::ScalarField::zero(); 2],
+    };
     let x = p_xy
         .0
         .to_base_prime_field_elements()
@@ -72,20 +115,35 @@ fn prepare_point<C: CurveGroup>(p: &C) -> Vec<C::ScalarField> {
 
 #[cfg(test)]
 pub mod tests {
+    use super::*;
     use ark_crypto_primitives::sponge::poseidon::{find_poseidon_ark_and_mds, PoseidonConfig};
     use ark_ff::PrimeField;
     use ark_pallas::{Fr, Projective};
-    use super::*;
 
+    use crate::utils::sum_check::tests::{Goldilocks, GoldilocksExt2};
+
+    // x^alpha must be a permutation, so alpha is the smallest prime not dividing
+    // p - 1: 5 for the curve scalar fields and 7 for Goldilocks. The capacity holds
+    // at least 128 bits, two elements of a 64 bit field.
     pub fn poseidon_test_config<F: PrimeField>() -> PoseidonConfig<F> {
         let full_rounds = 8;
         let partial_rounds = 31;
-        let alpha = 5;
+        let alpha = [3u64, 5, 7, 11, 13]
+            .into_iter()
+            .find(|a| {
+                let p_mod_a = F::characteristic().iter().rev().fold(0u128, |acc, limb| {
+                    ((acc << 64) + *limb as u128) % *a as u128
+                });
+                p_mod_a != 1
+            })
+            .unwrap();
         let rate = 2;
+        let capacity = 128usize.div_ceil(F::MODULUS_BIT_SIZE as usize);
 
+        // the width of the ark and mds is their rate + 1
         let (ark, mds) = find_poseidon_ark_and_mds(
             F::MODULUS_BIT_SIZE as u64,
-            rate,
+            rate + capacity - 1,
             full_rounds,
             partial_rounds,
             0,
@@ -97,13 +155,27 @@ pub mod tests {
             mds,
             ark,
             rate,
-            1,
+            capacity,
         )
     }
 
+    #[test]
+    fn test_poseidon_test_config() {
+        let config = poseidon_test_config::<Fr>();
+        assert_eq!((config.alpha, config.rate, config.capacity), (5, 2, 1));
+        let config = poseidon_test_config::<Goldilocks>();
+        assert_eq!((config.alpha, config.rate, config.capacity), (7, 2, 2));
+        assert_eq!(config.mds.len(), 4);
+
+        let mut tr = PoseidonFieldTranscript::new(&config);
+        tr.absorb_ext(&[GoldilocksExt2::from(3u64)]);
+        let c: GoldilocksExt2 = tr.challenge_ext();
+        assert!(!c.c1.is_zero());
+    }
+
     #[test]
     fn test_transcript_challenge() {
-        let config = poseidon_test_config::<Fr>(); 
+        let config = poseidon_test_config::<Fr>();
         let mut tr = PoseidonTranscript::<Projective>::new(&config);
         tr.absorb(&Fr::from(42u32));
         let _c = tr.get_challenge();
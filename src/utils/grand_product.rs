@@ -0,0 +1,202 @@
+use ark_ec::CurveGroup;
+use ark_ff::PrimeField;
+use ark_poly::MultilinearExtension;
+use ark_std::{One, Zero};
+use std::marker::PhantomData;
+use thiserror::Error;
+
+use super::mle::{eq_eval, eq_evals, vec_to_mle};
+use super::sum_check::{Error as SumCheckError, SumCheck, SumCheckProof, VirtualPolynomial};
+use crate::transcript::Transcript;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    SumCheck(#[from] SumCheckError),
+    #[error("Layer {0}: claimed children do not match the sum-check")]
+    LayerMismatch(usize),
+    #[error("Expected {0} layers, got {1}")]
+    InvalidNumLayers(usize, usize),
+    #[error("Expected {0} circuits, got {1}")]
+    InvalidNumCircuits(usize, usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayerProof<F: PrimeField> {
+    pub sum_check: SumCheckProof<F>,
+    // claimed evaluations of the even and odd children of every circuit
+    pub left: Vec<F>,
+    pub right: Vec<F>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GrandProductProof<F: PrimeField> {
+    // from the root down to the leaves
+    pub layers: Vec<LayerProof<F>>,
+}
+
+/// Batched product of several vectors of the same power of two length, proven
+/// layer by layer of the binary multiplication tree:
+/// V_i(b) = sum_y eq(b, y) * V_{i+1}(0, y) * V_{i+1}(1, y)
+pub struct GrandProduct<C: CurveGroup> {
+    _c: PhantomData<C>,
+}
+
+impl<C: CurveGroup> GrandProduct<C> {
+    /// Returns the products, the proof and the point at which the caller must
+    /// check the leaf vectors' MLEs.
+    #[allow(clippy::type_complexity)]
+    pub fn prove(
+        leaves: &[Vec<C::ScalarField>],
+        transcript: &mut impl Transcript<C>,
+    ) -> (
+        Vec<C::ScalarField>,
+        GrandProductProof<C::ScalarField>,
+        Vec<C::ScalarField>,
+    ) {
+        let num_vars = leaves[0].len().trailing_zeros() as usize;
+        // trees[j][i] is layer i of circuit j, layer 0 being the leaves
+        let trees: Vec<Vec<Vec<C::ScalarField>>> = leaves
+            .iter()
+            .map(|l| {
+                assert_eq!(l.len(), 1 << num_vars);
+                let mut layers = vec![l.clone()];
+                for i in 0..num_vars {
+                    let next = layers[i].chunks(2).map(|c| c[0] * c[1]).collect();
+                    layers.push(next);
+                }
+                layers
+            })
+            .collect();
+        let products: Vec<C::ScalarField> = trees.iter().map(|t| t[num_vars][0]).collect();
+        transcript.absorb_vec(&products);
+
+        let mut point = Vec::new();
+        let mut layers = Vec::with_capacity(num_vars);
+        for k in 0..num_vars {
+            // children live in layer num_vars - k - 1 and have k + 1 variables
+            let lambda = transcript.get_challenge();
+            let mut poly = VirtualPolynomial::new(k);
+            let eq = poly.add_mle(vec_to_mle(k, &eq_evals(&point)));
+            let mut lambda_j = C::ScalarField::one();
+            for tree in &trees {
+                let children = &tree[num_vars - k - 1];
+                let left: Vec<_> = children.iter().step_by(2).copied().collect();
+                let right: Vec<_> = children.iter().skip(1).step_by(2).copied().collect();
+                let l = poly.add_mle(vec_to_mle(k, &left));
+                let r = poly.add_mle(vec_to_mle(k, &right));
+                poly.add_product(lambda_j, vec![eq, l, r]);
+                lambda_j *= lambda;
+            }
+            let (sum_check, r_b) = SumCheck::prove(&poly, transcript);
+
+            let mut left = Vec::with_capacity(trees.len());
+            let mut right = Vec::with_capacity(trees.len());
+            for j in 0..trees.len() {
+                left.push(poly.mles[2 * j + 1].evaluate(&r_b).unwrap());
+                right.push(poly.mles[2 * j + 2].evaluate(&r_b).unwrap());
+            }
+            transcript.absorb_vec(&left);
+            transcript.absorb_vec(&right);
+            let c = transcript.get_challenge();
+            point = [vec![c], r_b].concat();
+            layers.push(LayerProof {
+                sum_check,
+                left,
+                right,
+            });
+        }
+        (products, GrandProductProof { layers }, point)
+    }
+
+    /// Returns the claimed evaluations of every leaf vector's MLE at the returned point
+    #[allow(clippy::type_complexity)]
+    pub fn verify(
+        products: &[C::ScalarField],
+        proof: &GrandProductProof<C::ScalarField>,
+        num_vars: usize,
+        transcript: &mut impl Transcript<C>,
+    ) -> Result<(Vec<C::ScalarField>, Vec<C::ScalarField>), Error> {
+        if proof.layers.len() != num_vars {
+            return Err(Error::InvalidNumLayers(num_vars, proof.layers.len()));
+        }
+        transcript.absorb_vec(products);
+
+        let mut claims = products.to_vec();
+        let mut point = Vec::new();
+        for (k, layer) in proof.layers.iter().enumerate() {
+            if layer.left.len() != products.len() || layer.right.len() != products.len() {
+                return Err(Error::InvalidNumCircuits(
+                    products.len(),
+                    layer.left.len().min(layer.right.len()),
+                ));
+            }
+            let lambda = transcript.get_challenge();
+            let mut claim = C::ScalarField::zero();
+            let mut lambda_j = C::ScalarField::one();
+            for c in &claims {
+                claim += lambda_j * c;
+                lambda_j *= lambda;
+            }
+            let (expected, r_b) = SumCheck::verify(claim, &layer.sum_check, k, 3, transcript)?;
+
+            let mut lambda_j = C::ScalarField::one();
+            let mut sum = C::ScalarField::zero();
+            for (l, r) in layer.left.iter().zip(&layer.right) {
+                sum += lambda_j * l * r;
+                lambda_j *= lambda;
+            }
+            if expected != eq_eval(&point, &r_b) * sum {
+                return Err(Error::LayerMismatch(k));
+            }
+            transcript.absorb_vec(&layer.left);
+            transcript.absorb_vec(&layer.right);
+            let c = transcript.get_challenge();
+            claims = layer
+                .left
+                .iter()
+                .zip(&layer.right)
+                .map(|(l, r)| *l + c * (*r - l))
+                .collect();
+            point = [vec![c], r_b].concat();
+        }
+        Ok((claims, point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_pallas::{Fr, Projective};
+    use ark_std::UniformRand;
+
+    use super::*;
+    use crate::transcript::poseidon::{tests::poseidon_test_config, PoseidonTranscript};
+
+    #[test]
+    fn test_grand_product() {
+        let mut rng = ark_std::test_rng();
+        let num_vars = 4;
+        let leaves: Vec<Vec<Fr>> = (0..3)
+            .map(|_| (0..1 << num_vars).map(|_| Fr::rand(&mut rng)).collect())
+            .collect();
+
+        let config = poseidon_test_config::<Fr>();
+        let mut ts_prove = PoseidonTranscript::<Projective>::new(&config);
+        let mut ts_verify = PoseidonTranscript::<Projective>::new(&config);
+        let (products, proof, point) = GrandProduct::prove(&leaves, &mut ts_prove);
+        for (p, l) in products.iter().zip(&leaves) {
+            assert_eq!(*p, l.iter().product::<Fr>());
+        }
+        let (claims, v_point) =
+            GrandProduct::verify(&products, &proof, num_vars, &mut ts_verify).unwrap();
+        assert_eq!(point, v_point);
+        for (c, l) in claims.iter().zip(&leaves) {
+            assert_eq!(*c, vec_to_mle(num_vars, l).evaluate(&point).unwrap());
+        }
+
+        let mut ts_verify = PoseidonTranscript::<Projective>::new(&config);
+        let mut wrong = products.clone();
+        wrong[0] += Fr::one();
+        assert!(GrandProduct::verify(&wrong, &proof, num_vars, &mut ts_verify).is_err());
+    }
+}
@@ -0,0 +1,122 @@
+use ark_crypto_primitives::sponge::poseidon::{PoseidonConfig, PoseidonSponge};
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ff::PrimeField;
+
+/// Poseidon hash of a list of field elements
+pub fn poseidon_hash<F: PrimeField + Absorb>(config: &PoseidonConfig<F>, input: &[F]) -> F {
+    let mut sponge = PoseidonSponge::new(config);
+    sponge.absorb(&input);
+    sponge.squeeze_field_elements(1)[0]
+}
+
+/// Binary Merkle tree over a power of two number of leaf hashes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleTree<F: PrimeField> {
+    // layers[0] are the leaf hashes, the last layer is the root
+    pub layers: Vec<Vec<F>>,
+}
+
+impl<F: PrimeField + Absorb> MerkleTree<F> {
+    pub fn new(config: &PoseidonConfig<F>, leaves: Vec<F>) -> Self {
+        assert!(leaves.len().is_power_of_two());
+        let mut layers = vec![leaves];
+        while layers.last().unwrap().len() > 1 {
+            let next = layers
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|c| poseidon_hash(config, c))
+                .collect();
+            layers.push(next);
+        }
+        Self { layers }
+    }
+
+    pub fn root(&self) -> F {
+        self.layers.last().unwrap()[0]
+    }
+
+    /// sibling hashes from the leaf up to the root
+    pub fn open(&self, index: usize) -> Vec<F> {
+        let mut path = Vec::with_capacity(self.layers.len() - 1);
+        let mut index = index;
+        for layer in &self.layers[..self.layers.len() - 1] {
+            path.push(layer[index ^ 1]);
+            index >>= 1;
+        }
+        path
+    }
+
+    /// checks the path of the leaf at index in a tree with 2^depth leaves
+    pub fn verify(
+        config: &PoseidonConfig<F>,
+        root: &F,
+        depth: usize,
+        index: usize,
+        leaf: F,
+        path: &[F],
+    ) -> bool {
+        if path.len() != depth || index.checked_shr(depth as u32).unwrap_or(0) != 0 {
+            return false;
+        }
+        let mut index = index;
+        let mut node = leaf;
+        for sibling in path {
+            node = if index & 1 == 0 {
+                poseidon_hash(config, &[node, *sibling])
+            } else {
+                poseidon_hash(config, &[*sibling, node])
+            };
+            index >>= 1;
+        }
+        node == *root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_pallas::Fr;
+    use ark_std::{One, UniformRand};
+
+    use super::*;
+    use crate::transcript::poseidon::tests::poseidon_test_config;
+
+    #[test]
+    fn test_merkle_tree() {
+        let mut rng = ark_std::test_rng();
+        let config = poseidon_test_config::<Fr>();
+        let leaves: Vec<Fr> = (0..16).map(|_| Fr::rand(&mut rng)).collect();
+        let tree = MerkleTree::new(&config, leaves.clone());
+        let root = tree.root();
+        for (i, leaf) in leaves.iter().enumerate() {
+            let path = tree.open(i);
+            assert!(MerkleTree::verify(&config, &root, 4, i, *leaf, &path));
+            assert!(!MerkleTree::verify(
+                &config,
+                &root,
+                4,
+                i,
+                *leaf + Fr::one(),
+                &path
+            ));
+            // the same path with a higher index bit, or an inner node passed as a leaf
+            assert!(!MerkleTree::verify(&config, &root, 4, i + 16, *leaf, &path));
+            assert!(MerkleTree::verify(
+                &config,
+                &root,
+                3,
+                i / 2,
+                tree.layers[1][i / 2],
+                &path[1..]
+            ));
+            assert!(!MerkleTree::verify(
+                &config,
+                &root,
+                4,
+                i / 2,
+                tree.layers[1][i / 2],
+                &path[1..]
+            ));
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use ark_ff::{Field, PrimeField};
+use ark_poly::DenseMultilinearExtension;
+
+/// eq(r, x) for every x in {0,1}^n, x_i is bit i of the index
+pub fn eq_evals<F: Field>(r: &[F]) -> Vec<F> {
+    let mut evals = vec![F::one()];
+    for r_i in r {
+        let hi: Vec<F> = evals.iter().map(|e| *e * r_i).collect();
+        for (e, h) in evals.iter_mut().zip(&hi) {
+            *e -= h;
+        }
+        evals.extend(hi);
+    }
+    evals
+}
+
+/// eq(a, b) = prod_i a_i * b_i + (1 - a_i) * (1 - b_i)
+pub fn eq_eval<F: Field>(a: &[F], b: &[F]) -> F {
+    a.iter()
+        .zip(b)
+        .map(|(a_i, b_i)| *a_i * b_i + (F::one() - a_i) * (F::one() - b_i))
+        .product()
+}
+
+/// MLE of the identity x -> sum_i 2^i * x_i
+pub fn identity_eval<F: PrimeField>(r: &[F]) -> F {
+    r.iter()
+        .rev()
+        .fold(F::zero(), |acc, r_i| acc.double() + r_i)
+}
+
+/// vector padded with zeros to 2^num_vars evaluations
+pub fn vec_to_mle<F: PrimeField>(num_vars: usize, v: &[F]) -> DenseMultilinearExtension<F> {
+    let mut evals = v.to_vec();
+    evals.resize(1 << num_vars, F::zero());
+    DenseMultilinearExtension::from_evaluations_vec(num_vars, evals)
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_pallas::Fr;
+    use ark_poly::MultilinearExtension;
+    use ark_std::UniformRand;
+
+    use super::*;
+
+    #[test]
+    fn test_eq_and_identity() {
+        let mut rng = ark_std::test_rng();
+        let r: Vec<Fr> = (0..4).map(|_| Fr::rand(&mut rng)).collect();
+        let point: Vec<Fr> = (0..4).map(|_| Fr::rand(&mut rng)).collect();
+
+        let eq = vec_to_mle(4, &eq_evals(&r));
+        assert_eq!(eq.evaluate(&point).unwrap(), eq_eval(&r, &point));
+
+        let id: Vec<Fr> = (0..16u64).map(Fr::from).collect();
+        let id = vec_to_mle(4, &id);
+        assert_eq!(id.evaluate(&point).unwrap(), identity_eval(&point));
+    }
+}
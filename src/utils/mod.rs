@@ -0,0 +1,4 @@
+pub mod grand_product;
+pub mod merkle;
+pub mod mle;
+pub mod sum_check;
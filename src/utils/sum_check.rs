@@ -0,0 +1,370 @@
+use ark_ff::{Field, PrimeField};
+use ark_poly::DenseMultilinearExtension;
+use std::marker::PhantomData;
+use thiserror::Error;
+
+use super::mle::eq_evals;
+use crate::transcript::FieldTranscript;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Round {0}: p(0) + p(1) does not match the claim")]
+    RoundClaimMismatch(usize),
+    #[error("Round {0}: expected {1} evaluations, got {2}")]
+    InvalidRoundPoly(usize, usize, usize),
+    #[error("Expected {0} rounds, got {1}")]
+    InvalidNumRounds(usize, usize),
+}
+
+/// sum_i c_i * prod_{j in S_i} mles[j], the same shape as the CCS relation
+#[derive(Clone, Debug)]
+pub struct VirtualPolynomial<F: PrimeField> {
+    pub num_vars: usize,
+    // max cardinality of the products
+    pub degree: usize,
+    pub products: Vec<(F, Vec<usize>)>,
+    pub mles: Vec<DenseMultilinearExtension<F>>,
+}
+
+impl<F: PrimeField> VirtualPolynomial<F> {
+    pub fn new(num_vars: usize) -> Self {
+        Self {
+            num_vars,
+            degree: 0,
+            products: Vec::new(),
+            mles: Vec::new(),
+        }
+    }
+
+    pub fn add_mle(&mut self, mle: DenseMultilinearExtension<F>) -> usize {
+        assert_eq!(mle.num_vars, self.num_vars);
+        self.mles.push(mle);
+        self.mles.len() - 1
+    }
+
+    pub fn add_product(&mut self, c: F, s: Vec<usize>) {
+        self.degree = self.degree.max(s.len());
+        self.products.push((c, s));
+    }
+
+    /// evaluates at a point of F or of an extension of F, the point of a sum-check
+    /// with extension challenges
+    pub fn evaluate<E: Field<BasePrimeField = F>>(&self, point: &[E]) -> E {
+        let eq = eq_evals(point);
+        let evals: Vec<E> = self
+            .mles
+            .iter()
+            .map(|m| {
+                eq.iter()
+                    .zip(&m.evaluations)
+                    .map(|(e, v)| *e * E::from_base_prime_field(*v))
+                    .sum()
+            })
+            .collect();
+        self.products
+            .iter()
+            .map(|(c, s)| E::from_base_prime_field(*c) * s.iter().map(|j| evals[*j]).product::<E>())
+            .sum()
+    }
+
+    pub fn sum(&self) -> F {
+        (0..1 << self.num_vars)
+            .map(|b| {
+                self.products
+                    .iter()
+                    .map(|(c, s)| *c * s.iter().map(|j| self.mles[*j][b]).product::<F>())
+                    .sum::<F>()
+            })
+            .sum()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SumCheckProof<F: Field> {
+    // round polynomials given by their evaluations at 0, 1, .., degree
+    pub round_polys: Vec<Vec<F>>,
+}
+
+/// Sum-check of a polynomial over F with challenges in an extension E of F, E = F by
+/// default. Over a small field E must be large enough for the soundness error
+/// degree * num_vars / |E| to be negligible, e.g. a quadratic extension of Goldilocks.
+pub struct SumCheck<F: PrimeField, E: Field<BasePrimeField = F> = F> {
+    _f: PhantomData<(F, E)>,
+}
+
+impl<F: PrimeField, E: Field<BasePrimeField = F>> SumCheck<F, E> {
+    /// Proves the sum of poly over the boolean hypercube, binding x_0 first.
+    /// Returns the proof and the random point the verifier ends at.
+    pub fn prove(
+        poly: &VirtualPolynomial<F>,
+        transcript: &mut impl FieldTranscript<F>,
+    ) -> (SumCheckProof<E>, Vec<E>) {
+        Self::prove_inner(poly, None, transcript)
+    }
+
+    /// Proves the sum of eq(beta, x) * poly(x) with beta in E, so that a zero check
+    /// over a small field draws its random point from E. The rounds have degree
+    /// poly.degree + 1 and the verifier ends at eq(beta, point) * poly(point).
+    pub fn prove_with_eq(
+        poly: &VirtualPolynomial<F>,
+        beta: &[E],
+        transcript: &mut impl FieldTranscript<F>,
+    ) -> (SumCheckProof<E>, Vec<E>) {
+        assert_eq!(beta.len(), poly.num_vars);
+        Self::prove_inner(poly, Some(eq_evals(beta)), transcript)
+    }
+
+    fn prove_inner(
+        poly: &VirtualPolynomial<F>,
+        eq: Option<Vec<E>>,
+        transcript: &mut impl FieldTranscript<F>,
+    ) -> (SumCheckProof<E>, Vec<E>) {
+        // lifted to E, they are bound to challenges in E from the first round
+        let mut tables: Vec<Vec<E>> = poly
+            .mles
+            .iter()
+            .map(|m| {
+                m.evaluations
+                    .iter()
+                    .map(|v| E::from_base_prime_field(*v))
+                    .collect()
+            })
+            .collect();
+        let products: Vec<(E, &Vec<usize>)> = poly
+            .products
+            .iter()
+            .map(|(c, s)| (E::from_base_prime_field(*c), s))
+            .collect();
+        // eq is bound like the other tables and multiplies every product
+        let degree = poly.degree + eq.is_some() as usize;
+        let eq_index = eq.map(|eq| {
+            tables.push(eq);
+            tables.len() - 1
+        });
+        let mut round_polys = Vec::with_capacity(poly.num_vars);
+        let mut point = Vec::with_capacity(poly.num_vars);
+
+        for k in (0..poly.num_vars).rev() {
+            let mut evals = vec![E::zero(); degree + 1];
+            let mut at_t = vec![E::zero(); tables.len()];
+            for b in 0..1 << k {
+                for (t, eval) in evals.iter_mut().enumerate() {
+                    let t_f = E::from(t as u64);
+                    for (v, table) in at_t.iter_mut().zip(&tables) {
+                        *v = table[2 * b] + t_f * (table[2 * b + 1] - table[2 * b]);
+                    }
+                    let sum: E = products
+                        .iter()
+                        .map(|(c, s)| *c * s.iter().map(|j| at_t[*j]).product::<E>())
+                        .sum();
+                    *eval += eq_index.map_or(sum, |i| at_t[i] * sum);
+                }
+            }
+            transcript.absorb_ext(&evals);
+            let r = transcript.challenge_ext::<E>();
+            for table in tables.iter_mut() {
+                *table = (0..1 << k)
+                    .map(|b| table[2 * b] + r * (table[2 * b + 1] - table[2 * b]))
+                    .collect();
+            }
+            round_polys.push(evals);
+            point.push(r);
+        }
+        (SumCheckProof { round_polys }, point)
+    }
+
+    /// Checks the rounds against claim and returns the value the polynomial must
+    /// take at the returned point, which the caller checks.
+    pub fn verify(
+        claim: E,
+        proof: &SumCheckProof<E>,
+        num_vars: usize,
+        degree: usize,
+        transcript: &mut impl FieldTranscript<F>,
+    ) -> Result<(E, Vec<E>), Error> {
+        if proof.round_polys.len() != num_vars {
+            return Err(Error::InvalidNumRounds(num_vars, proof.round_polys.len()));
+        }
+        let mut claim = claim;
+        let mut point = Vec::with_capacity(num_vars);
+        for (i, evals) in proof.round_polys.iter().enumerate() {
+            if evals.len() != degree + 1 {
+                return Err(Error::InvalidRoundPoly(i, degree + 1, evals.len()));
+            }
+            if evals[0] + evals[1] != claim {
+                return Err(Error::RoundClaimMismatch(i));
+            }
+            transcript.absorb_ext(evals);
+            let r = transcript.challenge_ext::<E>();
+            claim = interpolate_uni_poly(evals, r);
+            point.push(r);
+        }
+        Ok((claim, point))
+    }
+}
+
+/// evaluates at x the polynomial of degree < evals.len() with p(i) = evals[i]
+pub fn interpolate_uni_poly<F: Field>(evals: &[F], x: F) -> F {
+    let n = evals.len();
+    let mut res = F::zero();
+    for (i, e_i) in evals.iter().enumerate() {
+        let mut num = F::one();
+        let mut den = F::one();
+        for j in (0..n).filter(|j| *j != i) {
+            num *= x - F::from(j as u64);
+            den *= F::from(i as u64) - F::from(j as u64);
+        }
+        res += *e_i * num * den.inverse().unwrap();
+    }
+    res
+}
+
+#[cfg(test)]
+// the MontConfig derive implements the config inside a function
+#[allow(non_local_definitions)]
+pub mod tests {
+    use ark_ff::fields::{Fp2, Fp2Config, Fp64, MontBackend, MontConfig};
+    use ark_ff::MontFp;
+    use ark_pallas::{Fr, Projective};
+    use ark_poly::MultilinearExtension;
+    use ark_std::rand::Rng;
+    use ark_std::{One, UniformRand, Zero};
+
+    use super::*;
+    use crate::transcript::poseidon::{
+        tests::poseidon_test_config, PoseidonFieldTranscript, PoseidonTranscript,
+    };
+    use crate::transcript::Transcript;
+    use crate::utils::mle::eq_eval;
+
+    // a 64 bit field with no curve over it, sum-check needs extension challenges there
+    #[derive(MontConfig)]
+    #[modulus = "18446744069414584321"]
+    #[generator = "7"]
+    pub struct GoldilocksConfig;
+    pub type Goldilocks = Fp64<MontBackend<GoldilocksConfig, 1>>;
+
+    pub struct GoldilocksExt2Config;
+    impl Fp2Config for GoldilocksExt2Config {
+        type Fp = Goldilocks;
+        // 7 generates the multiplicative group, so it is not a square
+        const NONRESIDUE: Goldilocks = MontFp!("7");
+        const FROBENIUS_COEFF_FP2_C1: &'static [Goldilocks] = &[MontFp!("1"), MontFp!("-1")];
+    }
+    pub type GoldilocksExt2 = Fp2<GoldilocksExt2Config>;
+
+    fn get_test_poly<F: PrimeField, R: Rng>(num_vars: usize, rng: &mut R) -> VirtualPolynomial<F> {
+        let mut poly = VirtualPolynomial::<F>::new(num_vars);
+        let a = poly.add_mle(DenseMultilinearExtension::rand(num_vars, rng));
+        let b = poly.add_mle(DenseMultilinearExtension::rand(num_vars, rng));
+        let c = poly.add_mle(DenseMultilinearExtension::rand(num_vars, rng));
+        poly.add_product(F::from(3u64), vec![a, b, c]);
+        poly.add_product(-F::one(), vec![c]);
+        poly.add_product(F::from(7u64), vec![a, a]);
+        poly
+    }
+
+    #[test]
+    fn test_sum_check() {
+        let mut rng = ark_std::test_rng();
+        let num_vars = 5;
+        let poly = get_test_poly::<Fr, _>(num_vars, &mut rng);
+        let claim = poly.sum();
+
+        let config = poseidon_test_config::<Fr>();
+        let mut ts_prove = PoseidonTranscript::<Projective>::new(&config);
+        let mut ts_verify = PoseidonTranscript::<Projective>::new(&config);
+        let (proof, point) = SumCheck::prove(&poly, &mut ts_prove);
+        let (expected, v_point) =
+            SumCheck::verify(claim, &proof, num_vars, poly.degree, &mut ts_verify).unwrap();
+        assert_eq!(point, v_point);
+        assert_eq!(expected, poly.evaluate(&point));
+        assert_eq!(poly.evaluate(&point), {
+            let evals: Vec<Fr> = poly
+                .mles
+                .iter()
+                .map(|m| m.evaluate(&point).unwrap())
+                .collect();
+            poly.products
+                .iter()
+                .map(|(c, s)| *c * s.iter().map(|j| evals[*j]).product::<Fr>())
+                .sum::<Fr>()
+        });
+
+        let mut ts_verify = PoseidonTranscript::<Projective>::new(&config);
+        let wrong = claim + Fr::one();
+        assert!(SumCheck::verify(wrong, &proof, num_vars, poly.degree, &mut ts_verify).is_err());
+    }
+
+    #[test]
+    fn test_sum_check_extension_challenges() {
+        let mut rng = ark_std::test_rng();
+        let num_vars = 6;
+        let poly = get_test_poly::<Goldilocks, _>(num_vars, &mut rng);
+        let claim = GoldilocksExt2::from_base_prime_field(poly.sum());
+
+        let config = poseidon_test_config::<Goldilocks>();
+        let mut ts_prove = PoseidonFieldTranscript::new(&config);
+        let mut ts_verify = PoseidonFieldTranscript::new(&config);
+        let (proof, point) = SumCheck::<Goldilocks, GoldilocksExt2>::prove(&poly, &mut ts_prove);
+        // the challenges are out of the base field
+        assert!(point.iter().all(|r| !r.c1.is_zero()));
+        let (expected, v_point) = SumCheck::<Goldilocks, GoldilocksExt2>::verify(
+            claim,
+            &proof,
+            num_vars,
+            poly.degree,
+            &mut ts_verify,
+        )
+        .unwrap();
+        assert_eq!(point, v_point);
+        assert_eq!(expected, poly.evaluate(&point));
+
+        let mut ts_verify = PoseidonFieldTranscript::new(&config);
+        let wrong = claim + GoldilocksExt2::one();
+        assert!(SumCheck::<Goldilocks, GoldilocksExt2>::verify(
+            wrong,
+            &proof,
+            num_vars,
+            poly.degree,
+            &mut ts_verify
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_sum_check_with_eq() {
+        let mut rng = ark_std::test_rng();
+        let num_vars = 4;
+        let poly = get_test_poly::<Goldilocks, _>(num_vars, &mut rng);
+        let beta: Vec<GoldilocksExt2> = (0..num_vars)
+            .map(|_| GoldilocksExt2::rand(&mut rng))
+            .collect();
+        // sum_x eq(beta, x) * poly(x)
+        let claim: GoldilocksExt2 = eq_evals(&beta)
+            .iter()
+            .enumerate()
+            .map(|(b, eq)| {
+                let bits: Vec<GoldilocksExt2> = (0..num_vars)
+                    .map(|i| GoldilocksExt2::from(((b >> i) & 1) as u64))
+                    .collect();
+                *eq * poly.evaluate(&bits)
+            })
+            .sum();
+
+        let config = poseidon_test_config::<Goldilocks>();
+        let mut ts_prove = PoseidonFieldTranscript::new(&config);
+        let mut ts_verify = PoseidonFieldTranscript::new(&config);
+        let (proof, point) =
+            SumCheck::<Goldilocks, GoldilocksExt2>::prove_with_eq(&poly, &beta, &mut ts_prove);
+        let (expected, _) = SumCheck::<Goldilocks, GoldilocksExt2>::verify(
+            claim,
+            &proof,
+            num_vars,
+            poly.degree + 1,
+            &mut ts_verify,
+        )
+        .unwrap();
+        assert_eq!(expected, eq_eval(&beta, &point) * poly.evaluate(&point));
+    }
+}